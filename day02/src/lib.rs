@@ -0,0 +1,27 @@
+//! Day 2's own Intcode interpreter, in a library so the fuzz crate can compare it with the
+//! shared computer.
+
+pub fn execute_program(mut program: Vec<usize>) -> Vec<usize> {
+    let mut ip = 0;
+    loop {
+        match program[ip] {
+            99 => break,
+            1 => {
+                let in1 = program[ip + 1];
+                let in2 = program[ip + 2];
+                let out = program[ip + 3];
+                program[out] = program[in1] + program[in2];
+                ip += 4;
+            }
+            2 => {
+                let in1 = program[ip + 1];
+                let in2 = program[ip + 2];
+                let out = program[ip + 3];
+                program[out] = program[in1] * program[in2];
+                ip += 4;
+            }
+            _ => panic!("Unexpected opcode!"),
+        }
+    }
+    program
+}
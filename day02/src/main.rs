@@ -1,3 +1,5 @@
+use day02::execute_program;
+
 fn main() {
     let input = include_str!("../input.txt");
 
@@ -37,28 +39,3 @@ fn part_2(program: &[usize]) -> (usize, usize) {
     }
     panic!("No result found!");
 }
-
-fn execute_program(mut program: Vec<usize>) -> Vec<usize> {
-    let mut ip = 0;
-    loop {
-        match program[ip] {
-            99 => break,
-            1 => {
-                let in1 = program[ip + 1];
-                let in2 = program[ip + 2];
-                let out = program[ip + 3];
-                program[out] = program[in1] + program[in2];
-                ip += 4;
-            }
-            2 => {
-                let in1 = program[ip + 1];
-                let in2 = program[ip + 2];
-                let out = program[ip + 3];
-                program[out] = program[in1] * program[in2];
-                ip += 4;
-            }
-            _ => panic!("Unexpected opcode!"),
-        }
-    }
-    program
-}
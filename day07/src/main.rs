@@ -1,6 +1,4 @@
-mod computer;
-
-use computer::Computer;
+use intcode::{parse_program, Computer, ComputerState, Program, Runtime};
 
 struct Amplifier {
     computer: Computer,
@@ -17,7 +15,7 @@ fn main() {
     println!("Part 2: {}", result);
 }
 
-fn part_1(program: Vec<isize>) -> isize {
+fn part_1(program: Program) -> i64 {
     let mut max_out = 0;
 
     let input = [0, 1, 2, 3, 4];
@@ -44,30 +42,31 @@ fn part_1(program: Vec<isize>) -> isize {
     max_out
 }
 
-fn part_2(program: Vec<isize>) -> isize {
+fn part_2(program: Program) -> i64 {
     let mut max_out = 0;
 
     let input = [5, 6, 7, 8, 9];
     let permutations = generate_permutations(&input);
 
     for permutation in permutations {
-        let mut amp_a = Amplifier::new(program.clone(), permutation[0]);
-        let mut amp_b = Amplifier::new(program.clone(), permutation[1]);
-        let mut amp_c = Amplifier::new(program.clone(), permutation[2]);
-        let mut amp_d = Amplifier::new(program.clone(), permutation[3]);
-        let mut amp_e = Amplifier::new(program.clone(), permutation[4]);
-
-        let mut feedback = 0;
-        loop {
-            let a_out = match amp_a.execute(feedback) {
-                Some(val) => val,
-                None => break,
-            };
-            let b_out = amp_b.execute(a_out).expect("No output for b");
-            let c_out = amp_c.execute(b_out).expect("No output for c");
-            let d_out = amp_d.execute(c_out).expect("No output for d");
-            feedback = amp_e.execute(d_out).expect("No output for e");
+        let mut runtime = Runtime::new();
+        let amps: Vec<usize> = permutation
+            .iter()
+            .map(|&phase_setting| {
+                runtime.add(Amplifier::new(program.clone(), phase_setting).computer)
+            })
+            .collect();
+        for pair in amps.windows(2) {
+            runtime.connect(pair[0], pair[1]);
         }
+        // Feed the last amplifier back into the first
+        runtime.connect(amps[4], amps[0]);
+        runtime.push_input(amps[0], 0);
+        runtime.collect(amps[4]);
+
+        let mut finished = runtime.run().unwrap();
+        assert!(!finished.deadlock, "Amplifiers deadlocked");
+        let feedback = finished.outputs[amps[4]].pop().expect("No output for e");
 
         if feedback > max_out {
             max_out = feedback;
@@ -77,13 +76,13 @@ fn part_2(program: Vec<isize>) -> isize {
     max_out
 }
 
-fn generate_permutations(input: &[isize]) -> Vec<Vec<isize>> {
+fn generate_permutations(input: &[i64]) -> Vec<Vec<i64>> {
     let mut permutations = vec![];
     heap_permutation(&mut input.to_owned(), input.len(), &mut permutations);
     permutations
 }
 
-fn heap_permutation(current: &mut Vec<isize>, size: usize, permutations: &mut Vec<Vec<isize>>) {
+fn heap_permutation(current: &mut Vec<i64>, size: usize, permutations: &mut Vec<Vec<i64>>) {
     if size == 1 {
         permutations.push(current.clone());
         return;
@@ -100,23 +99,18 @@ fn heap_permutation(current: &mut Vec<isize>, size: usize, permutations: &mut Ve
     }
 }
 
-fn parse_program(input: &str) -> Vec<isize> {
-    input
-        .split(",")
-        .map(|num| num.parse::<isize>().unwrap())
-        .collect()
-}
-
 impl Amplifier {
-    fn new(program: Vec<isize>, phase_setting: isize) -> Amplifier {
+    fn new(program: Program, phase_setting: i64) -> Amplifier {
         let mut computer = Computer::new(program);
         computer.push_input(phase_setting);
         Amplifier { computer }
     }
 
-    fn execute(&mut self, input: isize) -> Option<isize> {
+    fn execute(&mut self, input: i64) -> Option<i64> {
         self.computer.push_input(input);
-        self.computer.execute();
-        self.computer.pop_output()
+        match self.computer.execute().unwrap() {
+            ComputerState::Output(output) => Some(output),
+            _ => None,
+        }
     }
 }
@@ -1,6 +1,4 @@
-mod computer;
-
-use computer::Computer;
+use intcode::{parse_program, Computer, ComputerState};
 
 fn main() {
     let input = include_str!("../input.txt");
@@ -9,7 +7,7 @@ fn main() {
     computer.push_input(1);
 
     println!("Part 1:");
-    while let Some(output) = computer.execute() {
+    while let ComputerState::Output(output) = computer.execute().unwrap() {
         println!("Output: {}", output);
     }
 
@@ -17,15 +15,7 @@ fn main() {
     computer.push_input(2);
 
     println!("Part 2:");
-    while let Some(output) = computer.execute() {
+    while let ComputerState::Output(output) = computer.execute().unwrap() {
         println!("Output: {}", output);
     }
 }
-
-fn parse_program(input: &str) -> Vec<isize> {
-    input
-        .trim()
-        .split(",")
-        .map(|num| num.parse::<isize>().unwrap())
-        .collect()
-}
@@ -1,6 +1,4 @@
-mod computer;
-
-use computer::{parse_program, Computer};
+use intcode::{parse_program, Computer, ComputerState, Program};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Copy)]
@@ -40,7 +38,7 @@ impl Direction {
         }
     }
 
-    fn move_dir(&self, current: (isize, isize)) -> (isize, isize) {
+    fn move_dir(&self, current: (i64, i64)) -> (i64, i64) {
         match *self {
             Direction::Up => (current.0, current.1 + 1),
             Direction::Down => (current.0, current.1 - 1),
@@ -60,20 +58,24 @@ fn main() {
     part2(program);
 }
 
-fn part1(program: Vec<isize>) -> usize {
+fn part1(program: Program) -> usize {
     let mut computer = Computer::new(program);
     let mut painted_tiles = HashMap::new();
     let mut position = (0, 0);
     let mut direction = Direction::Up;
     computer.push_input(0);
-    while let Some(paint) = computer.execute() {
+    while let ComputerState::Output(paint) = computer.execute().unwrap() {
         let color = match paint {
             0 => Color::Black,
             1 => Color::White,
             _ => panic!("Invalid color"),
         };
         painted_tiles.insert(position, color);
-        direction = direction.turn(computer.execute().expect("No second output") as usize);
+        let turn = match computer.execute().unwrap() {
+            ComputerState::Output(output) => output,
+            _ => panic!("No second output"),
+        };
+        direction = direction.turn(turn as usize);
         position = direction.move_dir(position);
         match painted_tiles.get(&position).unwrap_or(&Color::Black) {
             Color::White => computer.push_input(1),
@@ -83,7 +85,7 @@ fn part1(program: Vec<isize>) -> usize {
     painted_tiles.len()
 }
 
-fn part2(program: Vec<isize>) {
+fn part2(program: Program) {
     let mut computer = Computer::new(program);
     let mut painted_tiles = HashMap::new();
     let mut position = (0, 0);
@@ -94,7 +96,7 @@ fn part2(program: Vec<isize>) {
     let mut min_y = 0;
     let mut direction = Direction::Up;
     computer.push_input(1);
-    while let Some(paint) = computer.execute() {
+    while let ComputerState::Output(paint) = computer.execute().unwrap() {
         let color = match paint {
             0 => Color::Black,
             1 => Color::White,
@@ -107,7 +109,11 @@ fn part2(program: Vec<isize>) {
             min_y = min_y.min(position.1);
         }
         painted_tiles.insert(position, color);
-        direction = direction.turn(computer.execute().expect("No second output") as usize);
+        let turn = match computer.execute().unwrap() {
+            ComputerState::Output(output) => output,
+            _ => panic!("No second output"),
+        };
+        direction = direction.turn(turn as usize);
         position = direction.move_dir(position);
         match painted_tiles.get(&position).unwrap_or(&Color::Black) {
             Color::White => computer.push_input(1),
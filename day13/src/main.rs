@@ -1,6 +1,4 @@
-mod computer;
-
-use computer::{parse_program, Computer, ComputerState, Program};
+use intcode::{parse_program, Computer, ComputerState, Program};
 use std::{cmp::Ordering, collections::HashMap};
 
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
@@ -13,7 +11,7 @@ enum TileId {
 }
 
 impl TileId {
-    fn from_int(input: isize) -> TileId {
+    fn from_int(input: i64) -> TileId {
         match input {
             0 => TileId::Empty,
             1 => TileId::Wall,
@@ -34,59 +32,50 @@ impl ArcadeBox {
         ArcadeBox { game }
     }
 
-    fn play(&mut self) -> isize {
+    fn play(&mut self) -> i64 {
         let mut program = self.game.clone();
         // enter 2 coins
         program[0] = 2;
         let mut computer = Computer::new(program);
+        computer.record_if_requested();
         let mut score = 0;
         let mut tiles = HashMap::new();
         let mut ball_location = (0, 0);
         let mut paddle_location = (0, 0);
         loop {
-            match computer.execute() {
-                ComputerState::Halt => return score,
-                ComputerState::InputRequired => {
-                    let block_count = count_blocks(&tiles);
-                    if block_count == 0 {
-                        return score;
-                    }
-
-                    let input = match ball_location.0.cmp(&paddle_location.0) {
-                        Ordering::Equal => 0,
-                        Ordering::Greater => 1,
-                        Ordering::Less => -1,
-                    };
-
-                    computer.push_input(input);
-                }
-                ComputerState::Output(x) => {
-                    let y = match computer.execute() {
-                        ComputerState::Output(output) => output,
-                        _ => panic!("Unexpected computer state."),
-                    };
-                    let id = match computer.execute() {
-                        ComputerState::Output(output) => output,
-                        _ => panic!("Unexpected computer state."),
-                    };
-                    if x == -1 && y == 0 {
-                        score = id;
-                    } else {
-                        let id = TileId::from_int(id);
-                        tiles.insert((x, y), id);
-                        match id {
-                            TileId::Ball => ball_location = (x, y),
-                            TileId::Paddle => paddle_location = (x, y),
-                            _ => {}
-                        }
+            let mut chunks = computer.output_chunks();
+            for chunk in &mut chunks {
+                let [x, y, id] = chunk.unwrap();
+                if x == -1 && y == 0 {
+                    score = id;
+                } else {
+                    let id = TileId::from_int(id);
+                    tiles.insert((x, y), id);
+                    match id {
+                        TileId::Ball => ball_location = (x, y),
+                        TileId::Paddle => paddle_location = (x, y),
+                        _ => {}
                     }
                 }
             }
+            if chunks.stopped() == Some(&ComputerState::Halt) || count_blocks(&tiles) == 0 {
+                computer
+                    .save_requested_recording()
+                    .expect("Failed to save recording");
+                return score;
+            }
+
+            let input = match ball_location.0.cmp(&paddle_location.0) {
+                Ordering::Equal => 0,
+                Ordering::Greater => 1,
+                Ordering::Less => -1,
+            };
+            computer.push_input(input);
         }
     }
 }
 
-fn count_blocks(tiles: &HashMap<(isize, isize), TileId>) -> usize {
+fn count_blocks(tiles: &HashMap<(i64, i64), TileId>) -> usize {
     tiles.values().filter(|id| **id == TileId::Block).count()
 }
 
@@ -103,24 +92,16 @@ fn main() {
 fn part1(program: Program) -> usize {
     let mut computer = Computer::new(program);
     let mut blocks = 0;
-    while let ComputerState::Output(_x) = computer.execute() {
-        let _y = match computer.execute() {
-            ComputerState::Output(output) => output,
-            _ => panic!("Unexpected computer state."),
-        };
-        let id = match computer.execute() {
-            ComputerState::Output(output) => output,
-            _ => panic!("Unexpected computer state."),
-        };
-        let id = TileId::from_int(id);
-        if id == TileId::Block {
+    for chunk in computer.output_chunks() {
+        let [_x, _y, id] = chunk.unwrap();
+        if TileId::from_int(id) == TileId::Block {
             blocks += 1;
         }
     }
     blocks
 }
 
-fn part2(program: Program) -> isize {
+fn part2(program: Program) -> i64 {
     let mut arcade = ArcadeBox::new(program);
     arcade.play()
 }
@@ -1,6 +1,4 @@
-mod computer;
-
-use computer::{parse_program, Computer, ComputerState};
+use intcode::{parse_program, Computer, ComputerState};
 use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -19,7 +17,7 @@ enum Direction {
 }
 
 impl Direction {
-    fn as_int(&self) -> isize {
+    fn as_int(&self) -> i64 {
         match self {
             Direction::North => 1,
             Direction::South => 2,
@@ -28,7 +26,7 @@ impl Direction {
         }
     }
 
-    fn as_delta(&self) -> (isize, isize) {
+    fn as_delta(&self) -> (i64, i64) {
         match self {
             Direction::North => (0, 1),
             Direction::South => (0, -1),
@@ -40,7 +38,7 @@ impl Direction {
 
 #[derive(Clone)]
 struct RepairDroid {
-    location: (isize, isize),
+    location: (i64, i64),
     computer: Computer,
 }
 
@@ -49,7 +47,7 @@ struct RepairDroid {
 // }
 
 // struct Maze {
-//     map: HashMap<(isize, isize), Tile>,
+//     map: HashMap<(i64, i64), Tile>,
 //     program: Program,
 // }
 
@@ -61,7 +59,7 @@ struct RepairDroid {
 //         Maze { map, computer }
 //     }
 
-//     fn find_oxygen_system(&mut self) -> (isize, isize) {
+//     fn find_oxygen_system(&mut self) -> (i64, i64) {
 //         loop {
 //             self.print_map();
 //             println!("Move:");
@@ -128,7 +126,9 @@ fn main() {
     let input = include_str!("../input.txt");
     let program = parse_program(input);
     let mut map = HashMap::new();
-    let computer = Computer::new(program);
+    let mut computer = Computer::new(program);
+    // Records the way to the oxygen system.
+    computer.record_if_requested();
     explore_maze(computer, (0, 0), &mut map);
     let result1 = part1(&map);
     println!("Part 1: {}", result1);
@@ -137,7 +137,7 @@ fn main() {
     println!("Part 2: {}", result2);
 }
 
-fn part1(map: &HashMap<(isize, isize), Tile>) -> usize {
+fn part1(map: &HashMap<(i64, i64), Tile>) -> usize {
     // Pathfinding
     let mut open = Vec::new();
     open.push((0, 0));
@@ -177,7 +177,7 @@ fn part1(map: &HashMap<(isize, isize), Tile>) -> usize {
     steps
 }
 
-fn part2(map: &HashMap<(isize, isize), Tile>) -> usize {
+fn part2(map: &HashMap<(i64, i64), Tile>) -> usize {
     let (start, _) = map
         .iter()
         .find(|(_, tile)| **tile == Tile::OxygenSystem)
@@ -219,7 +219,7 @@ fn part2(map: &HashMap<(isize, isize), Tile>) -> usize {
 
 fn move_direction(computer: &mut Computer, direction: Direction) -> Tile {
     computer.push_input(direction.as_int());
-    match computer.execute() {
+    match computer.execute().unwrap() {
         ComputerState::Output(0) => Tile::Wall,
         ComputerState::Output(1) => Tile::Empty,
         ComputerState::Output(2) => Tile::OxygenSystem,
@@ -227,11 +227,7 @@ fn move_direction(computer: &mut Computer, direction: Direction) -> Tile {
     }
 }
 
-fn explore_maze(
-    computer: Computer,
-    position: (isize, isize),
-    map: &mut HashMap<(isize, isize), Tile>,
-) {
+fn explore_maze(computer: Computer, position: (i64, i64), map: &mut HashMap<(i64, i64), Tile>) {
     for direction in &[
         Direction::North,
         Direction::South,
@@ -246,6 +242,11 @@ fn explore_maze(
         }
         let tile = move_direction(&mut new_computer, *direction);
         map.insert(new_pos, tile);
+        if tile == Tile::OxygenSystem {
+            new_computer
+                .save_requested_recording()
+                .expect("Failed to save recording");
+        }
         match tile {
             Tile::Empty => explore_maze(new_computer, new_pos, map),
             _ => {}
@@ -254,7 +255,7 @@ fn explore_maze(
 }
 
 #[allow(dead_code)]
-fn print_map(map: &HashMap<(isize, isize), Tile>) {
+fn print_map(map: &HashMap<(i64, i64), Tile>) {
     let min_x = map.keys().map(|(x, _)| *x).min().unwrap();
     let max_x = map.keys().map(|(x, _)| *x).max().unwrap();
     let min_y = map.keys().map(|(_, y)| *y).min().unwrap();
@@ -1,9 +1,7 @@
-mod computer;
-
-use computer::{parse_program, Computer, ComputerState, Program};
+use intcode::{parse_program, AsciiComputer, AsciiOutput, Computer, Program};
 use std::collections::HashMap;
 
-type Map = HashMap<(isize, isize), Node>;
+type Map = HashMap<(i64, i64), Node>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Node {
@@ -21,7 +19,7 @@ enum Direction {
 }
 
 impl Direction {
-    fn apply(&self, pos: (isize, isize)) -> (isize, isize) {
+    fn apply(&self, pos: (i64, i64)) -> (i64, i64) {
         match *self {
             Direction::North => (pos.0, pos.1 - 1),
             Direction::South => (pos.0, pos.1 + 1),
@@ -68,7 +66,7 @@ fn main() {
     println!("Part 2: {}", result2);
 }
 
-fn part1(map: &Map) -> isize {
+fn part1(map: &Map) -> i64 {
     map.iter()
         .filter(|(_, node)| **node == Node::Scaffold)
         .filter(|(&(x, y), _)| {
@@ -81,7 +79,7 @@ fn part1(map: &Map) -> isize {
         .sum()
 }
 
-fn part2(map: &Map, mut program: Program) -> isize {
+fn part2(map: &Map, mut program: Program) -> i64 {
     let moves = find_moves(map);
     let mut moves_str = moves_to_string(&moves);
     moves_str.push(',');
@@ -90,25 +88,27 @@ fn part2(map: &Map, mut program: Program) -> isize {
 
     // Enable manual control mode
     program[0] = 2;
-    let mut computer = Computer::new(program);
-    send_string(&mut computer, &main_seq);
-    send_string(&mut computer, &a);
-    send_string(&mut computer, &b);
-    send_string(&mut computer, &c);
-    send_string(&mut computer, "n\n");
-
-    let mut last_output = 0;
-    while let ComputerState::Output(output) = computer.execute() {
-        last_output = output;
-    }
-    last_output
-}
+    let mut computer = AsciiComputer::new(Computer::new(program));
+    computer.computer.record_if_requested();
+    computer.send_line(&main_seq);
+    computer.send_line(&a);
+    computer.send_line(&b);
+    computer.send_line(&c);
+    computer.send_line("n");
 
-fn send_string(computer: &mut Computer, string: &str) {
-    for c in string.chars() {
-        computer.push_input(c as isize);
+    loop {
+        match computer.read().unwrap() {
+            AsciiOutput::Value(dust) => {
+                computer
+                    .computer
+                    .save_requested_recording()
+                    .expect("Failed to save recording");
+                return dust;
+            }
+            AsciiOutput::Line(_) => {}
+            _ => panic!("Robot stopped without reporting the collected dust"),
+        }
     }
-    computer.push_input(10);
 }
 
 // So, many people were doing this part by hand... I opted to not cave in and do it by hand,
@@ -224,31 +224,25 @@ fn moves_to_string(moves: &[Move]) -> String {
 }
 
 fn build_map(program: Program) -> Map {
-    let mut computer = Computer::new(program);
+    let mut computer = AsciiComputer::new(Computer::new(program));
 
     let mut map = HashMap::new();
     let mut y = 0;
-    let mut x = 0;
-
-    while let ComputerState::Output(output) = computer.execute() {
-        let node = match output as u8 as char {
-            '.' => Node::Empty,
-            '#' => Node::Scaffold,
-            '^' => Node::Robot(Direction::North),
-            'v' => Node::Robot(Direction::South),
-            '<' => Node::Robot(Direction::West),
-            '>' => Node::Robot(Direction::East),
-            '\n' => {
-                y += 1;
-                x = 0;
-                continue;
-            }
-            _ => panic!("Invalid computer output"),
-        };
 
-        map.insert((x, y), node);
-
-        x += 1;
+    while let AsciiOutput::Line(line) = computer.read().unwrap() {
+        for (x, c) in line.chars().enumerate() {
+            let node = match c {
+                '.' => Node::Empty,
+                '#' => Node::Scaffold,
+                '^' => Node::Robot(Direction::North),
+                'v' => Node::Robot(Direction::South),
+                '<' => Node::Robot(Direction::West),
+                '>' => Node::Robot(Direction::East),
+                _ => panic!("Invalid computer output"),
+            };
+            map.insert((x as i64, y), node);
+        }
+        y += 1;
     }
     map
 }
@@ -1,28 +1,27 @@
-mod computer;
-
-use computer::{parse_program, Computer, ComputerState, Program};
+use intcode::{parse_program, Computer, ComputerState};
 
 fn main() {
     let input = include_str!("../input.txt");
-    let program = parse_program(input);
+    // Every lookup clones this computer, sharing its decoded instructions.
+    let mut drone = Computer::new(parse_program(input));
+    drone.warm_decode_cache();
 
-    let result1 = part1(program.clone());
+    let result1 = part1(&drone);
     println!("Part 1: {}", result1);
 
-    let result2 = part2(program);
+    let result2 = part2(&drone);
     println!("Part 2: {}", result2);
 }
 
-
-fn part1(program: Program) -> usize {
+fn part1(drone: &Computer) -> usize {
     let mut sum = 0;
     for y in 0..50 {
         for x in 0..50 {
-            let mut computer = Computer::new(program.clone());
+            let mut computer = drone.clone();
             computer.push_input(x);
             computer.push_input(y);
 
-            if let ComputerState::Output(output) = computer.execute() {
+            if let ComputerState::Output(output) = computer.execute().unwrap() {
                 sum += output as usize;
             }
         }
@@ -30,29 +29,29 @@ fn part1(program: Program) -> usize {
     sum
 }
 
-fn lookup(program: Program, x: isize, y: isize) -> isize {
+fn lookup(drone: &Computer, x: i64, y: i64) -> i64 {
     if x < 0 || y < 0 {
         return 0;
     }
 
-    let mut computer = Computer::new(program.clone());
+    let mut computer = drone.clone();
     computer.push_input(x);
     computer.push_input(y);
-    match computer.execute() {
+    match computer.execute().unwrap() {
         ComputerState::Output(output) => output,
         _ => panic!("Unexpected computer output"),
     }
 }
 
-fn part2(program: Program) -> usize {
+fn part2(drone: &Computer) -> usize {
     // This is pretty slow and we skip the first 20 rows since they have no laser output which causes an infinite loop.
     let mut y = 20;
     loop {
         let mut x = 0;
         loop {
-            let output = lookup(program.clone(), x, y);
+            let output = lookup(drone, x, y);
             if output == 1 {
-                let corner = lookup(program.clone(), x + 99, y - 99);
+                let corner = lookup(drone, x + 99, y - 99);
                 if corner == 1 {
                     return (x * 10000 + y - 99) as usize;
                 }
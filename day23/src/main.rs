@@ -1,6 +1,4 @@
-mod computer;
-
-use computer::{parse_program, Computer, ComputerState, Program};
+use intcode::{parse_program, Computer, ComputerState, Program};
 use std::collections::{HashMap, HashSet};
 
 fn main() {
@@ -14,18 +12,18 @@ fn main() {
     println!("Part 2: {}", result2);
 }
 
-fn part1(program: Program) -> isize {
-    let mut queue: HashMap<usize, Vec<(isize, isize)>> = HashMap::new();
+fn part1(program: Program) -> i64 {
+    let mut queue: HashMap<usize, Vec<(i64, i64)>> = HashMap::new();
     let mut computers = vec![Computer::new(program.clone()); 50];
 
     for (i, computer) in computers.iter_mut().enumerate() {
-        computer.push_input(i as isize);
-        computer.execute();
+        computer.push_input(i as i64);
+        computer.execute().unwrap();
     }
 
     loop {
         for (i, computer) in computers.iter_mut().enumerate() {
-            match computer.execute() {
+            match computer.execute().unwrap() {
                 ComputerState::InputRequired => match queue.entry(i).or_default().pop() {
                     None => computer.push_input(-1),
                     Some((x, y)) => {
@@ -34,14 +32,7 @@ fn part1(program: Program) -> isize {
                     }
                 },
                 ComputerState::Output(address) => {
-                    let x = match computer.execute() {
-                        ComputerState::Output(output) => output,
-                        _ => panic!("Unexpected computer state!"),
-                    };
-                    let y = match computer.execute() {
-                        ComputerState::Output(output) => output,
-                        _ => panic!("Unexpected computer state!"),
-                    };
+                    let [x, y] = computer.take_outputs().unwrap();
                     if address == 255 {
                         return y;
                     } else {
@@ -54,13 +45,13 @@ fn part1(program: Program) -> isize {
     }
 }
 
-fn part2(program: Program) -> isize {
-    let mut queue: HashMap<usize, Vec<(isize, isize)>> = HashMap::new();
+fn part2(program: Program) -> i64 {
+    let mut queue: HashMap<usize, Vec<(i64, i64)>> = HashMap::new();
     let mut computers = vec![Computer::new(program.clone()); 50];
 
     for (i, computer) in computers.iter_mut().enumerate() {
-        computer.push_input(i as isize);
-        computer.execute();
+        computer.push_input(i as i64);
+        computer.execute().unwrap();
     }
 
     let mut nat_packet = (0, 0);
@@ -69,7 +60,7 @@ fn part2(program: Program) -> isize {
 
     loop {
         for (i, computer) in computers.iter_mut().enumerate() {
-            match computer.execute() {
+            match computer.execute().unwrap() {
                 ComputerState::InputRequired => match queue.entry(i).or_default().pop() {
                     None => {
                         computer.push_input(-1);
@@ -82,14 +73,7 @@ fn part2(program: Program) -> isize {
                     }
                 },
                 ComputerState::Output(address) => {
-                    let x = match computer.execute() {
-                        ComputerState::Output(output) => output,
-                        _ => panic!("Unexpected computer state!"),
-                    };
-                    let y = match computer.execute() {
-                        ComputerState::Output(output) => output,
-                        _ => panic!("Unexpected computer state!"),
-                    };
+                    let [x, y] = computer.take_outputs().unwrap();
                     if address == 255 {
                         nat_packet = (x, y);
                     } else {
@@ -0,0 +1,135 @@
+//! Random Intcode programs that are mostly well-formed: instructions are laid out one after the
+//! other, jumps mostly land on instructions, and addresses mostly point into or just past the
+//! program. A small share of parameters and opcodes are broken on purpose so that every way a
+//! program can fail gets exercised too.
+
+/// A small xorshift generator, so a case can be reproduced from its seed alone.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Zero is a fixed point of xorshift, and nearby seeds should give unrelated cases.
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A number in `low..high`.
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + (self.next_u64() % (high - low) as u64) as i64
+    }
+
+    /// True `percent` times out of a hundred.
+    pub fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+}
+
+/// A program together with the input it is given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    pub program: Vec<i64>,
+    pub inputs: Vec<i64>,
+}
+
+// Opcodes, with `ADD`, `MUL` and the jumps weighted up a little.
+const OPCODES: &[i64] = &[1, 1, 2, 2, 3, 4, 5, 5, 6, 6, 7, 8, 9];
+
+fn parameter_count(opcode: i64) -> usize {
+    match opcode {
+        1 | 2 | 7 | 8 => 3,
+        5 | 6 => 2,
+        3 | 4 | 9 => 1,
+        _ => 0,
+    }
+}
+
+fn writes(opcode: i64) -> bool {
+    [1, 2, 3, 7, 8].contains(&opcode)
+}
+
+/// A program for the whole instruction set, and some input for it.
+pub fn case(rng: &mut Rng) -> Case {
+    let count = 1 + rng.below(40);
+    let opcodes: Vec<i64> = (0..count)
+        .map(|_| OPCODES[rng.below(OPCODES.len())])
+        .collect();
+    let mut starts = Vec::with_capacity(count);
+    let mut len = 0;
+    for &opcode in &opcodes {
+        starts.push(len as i64);
+        len += parameter_count(opcode) + 1;
+    }
+    // Addresses reach a little past the end, into memory the program has to grow.
+    let region = len as i64 + 16;
+
+    let mut program = Vec::with_capacity(len + 1);
+    for &opcode in &opcodes {
+        let params = parameter_count(opcode);
+        let mut word = opcode;
+        let mut values = Vec::with_capacity(params);
+        for n in 0..params {
+            let write = writes(opcode) && n == params - 1;
+            let jump = (opcode == 5 || opcode == 6) && n == 1;
+            let mode = match rng.below(100) {
+                _ if jump && rng.chance(70) => 1,
+                0..=44 => 0,
+                45..=79 if !write => 1,
+                _ => 2,
+            };
+            let value = match mode {
+                1 if jump => starts[rng.below(count)],
+                1 if opcode == 9 => rng.range(-5, 10),
+                1 => rng.range(-5, 20),
+                2 => rng.range(-10, region),
+                _ => rng.range(0, region),
+            };
+            word += mode * 10i64.pow(n as u32 + 2);
+            values.push(value);
+        }
+        program.push(word);
+        program.extend(values);
+    }
+    program.push(99);
+
+    // Now and then, break something.
+    if rng.chance(20) {
+        let address = rng.below(program.len());
+        program[address] = match rng.below(6) {
+            0 => rng.range(10, 99),
+            1 => program[address] % 100 + 100 * rng.range(3, 10),
+            2 => program[address] % 100 + 11_100,
+            3 => -rng.range(1, 10),
+            4 => 1 << rng.range(20, 50),
+            _ => [i64::MAX, i64::MIN][rng.below(2)],
+        };
+    }
+
+    let inputs = (0..rng.below(8)).map(|_| rng.range(-3, 10)).collect();
+    Case { program, inputs }
+}
+
+/// A program for day 2's interpreter: only `ADD`, `MUL` and `HALT`, with every parameter in
+/// position mode and pointing into the program.
+pub fn day02_program(rng: &mut Rng) -> Vec<usize> {
+    let count = 1 + rng.below(12);
+    let len = count * 4 + 1;
+    let mut program = Vec::with_capacity(len);
+    for _ in 0..count {
+        program.push(1 + rng.below(2));
+        program.extend((0..3).map(|_| rng.below(len)));
+    }
+    program.push(99);
+    program
+}
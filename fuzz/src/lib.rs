@@ -0,0 +1,187 @@
+//! Differential fuzzing of the Intcode interpreters.
+//!
+//! Every case is a random program from `generate`, run on the shared `intcode::Computer`, once
+//! as is and once with its decode cache warmed up, and on the independent interpreter in
+//! `reference`. Any difference in outputs, final memory, position or the way the run ended is a
+//! `Divergence`. Day 2's own interpreter, which only knows `ADD`, `MUL` and `HALT`, is compared on
+//! programs of just those instructions.
+//!
+//! Day 5 used to have an interpreter of its own as well, but it was replaced by the shared
+//! computer when every day moved over to it, so the reference interpreter takes its place as the
+//! independent implementation for the full instruction set.
+
+pub mod generate;
+pub mod reference;
+
+use generate::{Case, Rng};
+use intcode::{Computer, StepResult};
+use reference::{Outcome, Stop};
+use std::{cell::Cell, fmt, panic, sync::Once};
+
+/// Number of instructions a case may execute, most programs loop forever.
+pub const MAX_STEPS: usize = 1_000;
+
+/// A case on which an interpreter disagreed with the reference.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub seed: u64,
+    /// The interpreter that disagreed.
+    pub subject: &'static str,
+    pub case: Case,
+    pub expected: Outcome,
+    pub actual: Outcome,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let words = |words: &[i64]| {
+            words
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        writeln!(f, "{} diverges on seed {}", self.subject, self.seed)?;
+        writeln!(f, "program: {}", words(&self.case.program))?;
+        writeln!(f, "inputs: {}", words(&self.case.inputs))?;
+        writeln!(f, "expected: {:?}", self.expected)?;
+        write!(f, "actual: {:?}", self.actual)
+    }
+}
+
+/// Runs the case generated from `seed` on the shared computer and the reference interpreter.
+pub fn check(seed: u64) -> Result<(), Box<Divergence>> {
+    let case = generate::case(&mut Rng::new(seed));
+    let expected = reference::run(&case.program, &case.inputs, MAX_STEPS);
+
+    let cold = Computer::new(case.program.clone());
+    let mut warm = cold.clone();
+    warm.warm_decode_cache();
+    for (subject, computer) in [("Computer", cold), ("Computer (warm cache)", warm)] {
+        let actual = run_computer(computer, &case.inputs);
+        if actual != expected {
+            return Err(Box::new(Divergence {
+                seed,
+                subject,
+                case,
+                expected,
+                actual,
+            }));
+        }
+    }
+    Ok(())
+}
+
+/// Runs the ADD/MUL program generated from `seed` on day 2's interpreter, the shared computer
+/// and the reference interpreter. Day 2 only gives back its memory, so for it just that is
+/// compared, and it has to panic exactly when the reference fails on the same, more limited
+/// machine. Programs that modify themselves can turn into anything, so that happens a lot.
+pub fn check_day02(seed: u64) -> Result<(), Box<Divergence>> {
+    let program = generate::day02_program(&mut Rng::new(seed));
+    let case = Case {
+        program: program.iter().map(|&word| word as i64).collect(),
+        inputs: Vec::new(),
+    };
+    let shared = run_computer(Computer::new(case.program.clone()), &[]);
+    let expected = reference::run(&case.program, &[], MAX_STEPS);
+    let (subject, actual, expected) = if shared != expected {
+        ("Computer", shared, expected)
+    } else {
+        let expected = reference::run_day02(&case.program, MAX_STEPS);
+        // What day 2 can't tell us is taken from the reference.
+        let actual = match run_day02(program) {
+            Some(memory) => Outcome {
+                memory: memory
+                    .into_iter()
+                    .enumerate()
+                    .filter(|&(_, value)| value != 0)
+                    .map(|(address, value)| (address, value as i64))
+                    .collect(),
+                stop: Stop::Halt,
+                ..expected.clone()
+            },
+            None => Outcome {
+                stop: Stop::Panicked,
+                ..expected.clone()
+            },
+        };
+        let same = match (&expected.stop, &actual.stop) {
+            (Stop::Halt, Stop::Halt) => actual.memory == expected.memory,
+            (Stop::Error(_), Stop::Panicked) => true,
+            _ => false,
+        };
+        if same {
+            return Ok(());
+        }
+        ("day02", actual, expected)
+    };
+    Err(Box::new(Divergence {
+        seed,
+        subject,
+        case,
+        expected,
+        actual,
+    }))
+}
+
+// Runs `computer` step by step, so it stops after exactly as many instructions as the reference.
+fn run_computer(mut computer: Computer, inputs: &[i64]) -> Outcome {
+    for &input in inputs {
+        computer.push_input(input);
+    }
+    let mut outputs = Vec::new();
+    let mut stop = Stop::StepLimit;
+    for _ in 0..MAX_STEPS {
+        match computer.step() {
+            Ok(StepResult::Continue) => {}
+            Ok(StepResult::Output(output)) => outputs.push(output),
+            Ok(StepResult::Stop) => {
+                stop = Stop::Halt;
+                break;
+            }
+            Ok(StepResult::InputRequired) => {
+                stop = Stop::InputRequired;
+                break;
+            }
+            Err(err) => {
+                stop = Stop::Error(err);
+                break;
+            }
+        }
+    }
+    Outcome {
+        outputs,
+        stop,
+        ip: computer.ip(),
+        relative_base: computer.relative_base(),
+        memory: computer
+            .memory()
+            .nonzero_words()
+            .map(|(address, &value)| (address, value))
+            .collect(),
+    }
+}
+
+thread_local! {
+    // Whether this thread is running day 2, whose panics aren't worth reporting.
+    static EXPECTING_PANICS: Cell<bool> = const { Cell::new(false) };
+}
+
+// Day 2's interpreter panics on anything it doesn't expect, `None` if it did.
+fn run_day02(program: Vec<usize>) -> Option<Vec<usize>> {
+    // The panic hook is global, so it is only replaced once, by one that stays quiet for the
+    // threads running day 2 and leaves every other panic to the previous hook.
+    static QUIET_HOOK: Once = Once::new();
+    QUIET_HOOK.call_once(|| {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !EXPECTING_PANICS.with(Cell::get) {
+                hook(info);
+            }
+        }));
+    });
+    EXPECTING_PANICS.with(|expecting| expecting.set(true));
+    let memory = panic::catch_unwind(|| day02::execute_program(program)).ok();
+    EXPECTING_PANICS.with(|expecting| expecting.set(false));
+    memory
+}
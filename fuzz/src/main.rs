@@ -0,0 +1,21 @@
+use std::{env, process};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let number = |index: usize, default: u64| match args.get(index) {
+        Some(arg) => arg.parse().unwrap_or_else(|_| {
+            eprintln!("Usage: fuzz [cases] [first seed]");
+            process::exit(1);
+        }),
+        None => default,
+    };
+    let (cases, first) = (number(0, 100_000), number(1, 0));
+
+    for seed in first..first + cases {
+        if let Err(divergence) = fuzz::check(seed).and_then(|_| fuzz::check_day02(seed)) {
+            eprintln!("{}", divergence);
+            process::exit(1);
+        }
+    }
+    println!("{} cases from seed {}, no divergences", cases, first);
+}
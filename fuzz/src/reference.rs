@@ -0,0 +1,233 @@
+//! A deliberately plain Intcode interpreter, written from the puzzle descriptions rather than
+//! from the shared `Computer`: memory is a map, every instruction is decoded from scratch and
+//! there is no caching of any kind. It only has to be obviously right, not fast.
+//!
+//! Where the puzzles leave behaviour open it follows the shared computer's documented choices:
+//! reading beyond the program yields zero, operands are read before the write address is
+//! checked, an `IN` with nothing to read leaves the machine untouched, and errors leave `ip` at
+//! the faulting instruction.
+
+use intcode::IntcodeError;
+use std::collections::{HashMap, VecDeque};
+
+/// Why a run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stop {
+    Halt,
+    InputRequired,
+    /// The run used up its steps.
+    StepLimit,
+    Error(IntcodeError),
+    /// Day 2's interpreter panicked, it has no errors of its own.
+    Panicked,
+}
+
+/// Everything observable about a finished run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub outputs: Vec<i64>,
+    pub stop: Stop,
+    pub ip: usize,
+    pub relative_base: i64,
+    /// Every non-zero word as `(address, value)`, in address order.
+    pub memory: Vec<(usize, i64)>,
+}
+
+struct Machine {
+    memory: HashMap<usize, i64>,
+    // Size of a memory that doesn't grow, see `run_day02`.
+    limit: Option<usize>,
+    ip: usize,
+    relative_base: i64,
+    inputs: VecDeque<i64>,
+    outputs: Vec<i64>,
+}
+
+/// Runs `program` with `inputs` for at most `max_steps` instructions.
+pub fn run(program: &[i64], inputs: &[i64], max_steps: usize) -> Outcome {
+    execute(program, inputs, max_steps, None)
+}
+
+/// Like `run`, but on the machine of day 2: the only instructions are `1`, `2` and `99`, without
+/// any parameter modes, and memory is the size of the program. Touching any address beyond it
+/// fails with `IntcodeError::AddressTooLarge`.
+pub fn run_day02(program: &[i64], max_steps: usize) -> Outcome {
+    execute(program, &[], max_steps, Some(program.len()))
+}
+
+fn execute(program: &[i64], inputs: &[i64], max_steps: usize, limit: Option<usize>) -> Outcome {
+    let mut machine = Machine {
+        memory: program.iter().copied().enumerate().collect(),
+        limit,
+        ip: 0,
+        relative_base: 0,
+        inputs: inputs.iter().copied().collect(),
+        outputs: Vec::new(),
+    };
+    let mut stop = Stop::StepLimit;
+    for _ in 0..max_steps {
+        match machine.step() {
+            Ok(None) => {}
+            Ok(Some(end)) => {
+                stop = end;
+                break;
+            }
+            Err(err) => {
+                stop = Stop::Error(err);
+                break;
+            }
+        }
+    }
+
+    let mut memory: Vec<(usize, i64)> = machine
+        .memory
+        .into_iter()
+        .filter(|&(_, value)| value != 0)
+        .collect();
+    memory.sort_unstable();
+    Outcome {
+        outputs: machine.outputs,
+        stop,
+        ip: machine.ip,
+        relative_base: machine.relative_base,
+        memory,
+    }
+}
+
+impl Machine {
+    // Reads a word for the instruction at `ip`.
+    fn get(&self, ip: usize, address: usize) -> Result<i64, IntcodeError> {
+        self.check(ip, address)?;
+        Ok(self.memory.get(&address).copied().unwrap_or(0))
+    }
+
+    fn check(&self, ip: usize, address: usize) -> Result<(), IntcodeError> {
+        match self.limit {
+            Some(limit) if address >= limit => Err(IntcodeError::AddressTooLarge {
+                ip,
+                opcode: self.memory.get(&ip).copied().unwrap_or(0),
+                address: address as i64,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Executes one instruction, returning how the run ended if it did.
+    fn step(&mut self) -> Result<Option<Stop>, IntcodeError> {
+        let ip = self.ip;
+        let opcode = self.get(ip, ip)?;
+        if self.limit.is_some() && ![1, 2, 99].contains(&opcode) {
+            return Err(IntcodeError::InvalidOpcode { ip, opcode });
+        }
+        let count = match opcode % 100 {
+            1 | 2 | 7 | 8 => 3,
+            5 | 6 => 2,
+            3 | 4 | 9 => 1,
+            99 => 0,
+            _ => return Err(IntcodeError::InvalidOpcode { ip, opcode }),
+        };
+
+        let mut modes = Vec::new();
+        let mut divisor = 100;
+        for _ in 0..count {
+            let mode = opcode / divisor % 10;
+            if mode > 2 {
+                return Err(IntcodeError::InvalidParameterMode { ip, opcode, mode });
+            }
+            modes.push(mode);
+            divisor *= 10;
+        }
+        let writes = [1, 2, 3, 7, 8].contains(&(opcode % 100));
+        if writes && modes[count - 1] == 1 {
+            return Err(IntcodeError::ImmediateWrite { ip, opcode });
+        }
+
+        let parameter = |n: usize| -> Result<(i64, i64), IntcodeError> {
+            Ok((modes[n], self.get(ip, ip + n + 1)?))
+        };
+        // Where a position or relative parameter points to.
+        let address = |(mode, value): (i64, i64)| -> Result<usize, IntcodeError> {
+            let address = if mode == 2 {
+                self.relative_base
+                    .checked_add(value)
+                    .ok_or(IntcodeError::Overflow { ip, opcode })?
+            } else {
+                value
+            };
+            if address < 0 {
+                return Err(IntcodeError::NegativeAddress {
+                    ip,
+                    opcode,
+                    address,
+                });
+            }
+            Ok(address as usize)
+        };
+        let read = |n: usize| -> Result<i64, IntcodeError> {
+            match parameter(n)? {
+                (1, value) => Ok(value),
+                parameter => self.get(ip, address(parameter)?),
+            }
+        };
+
+        let mut next = ip + count + 1;
+        let mut write = None;
+        match opcode % 100 {
+            1 | 2 => {
+                let (a, b) = (read(0)?, read(1)?);
+                let result = if opcode % 100 == 1 {
+                    a.checked_add(b)
+                } else {
+                    a.checked_mul(b)
+                };
+                let result = result.ok_or(IntcodeError::Overflow { ip, opcode })?;
+                write = Some((address(parameter(2)?)?, result));
+            }
+            3 => {
+                let target = address(parameter(0)?)?;
+                match self.inputs.pop_front() {
+                    Some(input) => write = Some((target, input)),
+                    None => return Ok(Some(Stop::InputRequired)),
+                }
+            }
+            4 => {
+                let output = read(0)?;
+                self.outputs.push(output);
+            }
+            5 | 6 => {
+                let condition = read(0)?;
+                if (condition != 0) == (opcode % 100 == 5) {
+                    let target = read(1)?;
+                    if target < 0 {
+                        return Err(IntcodeError::NegativeAddress {
+                            ip,
+                            opcode,
+                            address: target,
+                        });
+                    }
+                    next = target as usize;
+                }
+            }
+            7 | 8 => {
+                let (a, b) = (read(0)?, read(1)?);
+                let result = if opcode % 100 == 7 { a < b } else { a == b };
+                write = Some((address(parameter(2)?)?, result as i64));
+            }
+            9 => {
+                let offset = read(0)?;
+                self.relative_base = self
+                    .relative_base
+                    .checked_add(offset)
+                    .ok_or(IntcodeError::Overflow { ip, opcode })?;
+            }
+            _ => return Ok(Some(Stop::Halt)),
+        }
+
+        if let Some((address, value)) = write {
+            self.check(ip, address)?;
+            self.memory.insert(address, value);
+        }
+        self.ip = next;
+        Ok(None)
+    }
+}
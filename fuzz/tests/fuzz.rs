@@ -0,0 +1,31 @@
+//! A fixed batch of cases, so interpreter changes are checked against the reference on every
+//! test run. Run the `fuzz` binary for more.
+
+#[test]
+fn computer_matches_reference() {
+    for seed in 0..3_000 {
+        if let Err(divergence) = fuzz::check(seed) {
+            panic!("{}", divergence);
+        }
+    }
+}
+
+#[test]
+fn day02_matches_reference() {
+    for seed in 0..3_000 {
+        if let Err(divergence) = fuzz::check_day02(seed) {
+            panic!("{}", divergence);
+        }
+    }
+}
+
+// Cases that once diverged or hung.
+#[test]
+fn found_cases() {
+    // `find_code` looped forever on a return address that doesn't decode.
+    for &seed in &[161_599] {
+        if let Err(divergence) = fuzz::check(seed) {
+            panic!("{}", divergence);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Measures how expensive cloning computers is, using day15's maze exploration, which clones
+//! the droid's computer for every direction at every tile. As a baseline the same exploration
+//! runs on a bare interpreter whose memory is a flat `Vec<i64>`, copied in full by every clone.
+//!
+//! Paged memory cuts the bytes allocated about fivefold, but it makes more allocations than the
+//! baseline, roughly three and a half per move against one. Every clone allocates its own page
+//! table, the first input pushed into a clone allocates its input queue, and every page a move
+//! writes is copied on its own. day15's program is only about a thousand words, so copying all
+//! of it is one cheap allocation and both explorations take about the same time. The savings
+//! grow with the memory a program uses, not with the number of clones.
+//!
+//! Run with `cargo bench --bench clone`.
+
+use intcode::{parse_program, Computer, ComputerState};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+const ROUNDS: usize = 20;
+
+// Counts every allocation made through the global allocator.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn main() {
+    let program = parse_program(include_str!("../../day15/input.txt"));
+    let paged = measure(|| Computer::new(program.clone()));
+    let flat = measure(|| FlatComputer::new(program.clone()));
+    assert_eq!(
+        paged.tiles, flat.tiles,
+        "the explorations found different mazes"
+    );
+    println!("day15 exploration ({} tiles)", paged.tiles);
+    for (name, run) in [("paged memory", &paged), ("flat memory", &flat)] {
+        println!(
+            "  {:<14} {:>10.2?}  {:>7} allocations  {:>7.1} MiB allocated",
+            name,
+            run.best,
+            run.allocations,
+            run.allocated as f64 / (1024.0 * 1024.0)
+        );
+    }
+    println!(
+        "  paged / flat: {:.2}x the time, {:.2}x the allocations, {:.2}x the bytes",
+        paged.best.as_secs_f64() / flat.best.as_secs_f64(),
+        paged.allocations as f64 / flat.allocations as f64,
+        paged.allocated as f64 / flat.allocated as f64
+    );
+}
+
+// The fastest of ROUNDS explorations, with what its last round allocated.
+struct Run {
+    best: Duration,
+    allocations: usize,
+    allocated: usize,
+    tiles: usize,
+}
+
+fn measure<D: Droid, F: Fn() -> D>(new: F) -> Run {
+    let mut run = Run {
+        best: Duration::from_secs(u64::MAX),
+        allocations: 0,
+        allocated: 0,
+        tiles: 0,
+    };
+    for _ in 0..ROUNDS {
+        let droid = new();
+        let mut map = HashMap::new();
+        let (before, before_bytes) = (
+            ALLOCATIONS.load(Ordering::Relaxed),
+            ALLOCATED.load(Ordering::Relaxed),
+        );
+        let start = Instant::now();
+        explore(droid, (0, 0), &mut map);
+        run.best = run.best.min(start.elapsed());
+        run.allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        run.allocated = ALLOCATED.load(Ordering::Relaxed) - before_bytes;
+        run.tiles = map.len();
+    }
+    run
+}
+
+// A computer running the droid's program.
+trait Droid: Clone {
+    // Moves in `direction` and returns the status code.
+    fn step(&mut self, direction: i64) -> i64;
+}
+
+impl Droid for Computer {
+    fn step(&mut self, direction: i64) -> i64 {
+        self.push_input(direction);
+        match self.execute().unwrap() {
+            ComputerState::Output(tile) => tile,
+            state => panic!("expected output, got {:?}", state),
+        }
+    }
+}
+
+// The baseline: a bare interpreter keeping memory in one `Vec`, which every clone copies in
+// full.
+#[derive(Clone)]
+struct FlatComputer {
+    memory: Vec<i64>,
+    ip: usize,
+    relative_base: i64,
+}
+
+impl FlatComputer {
+    fn new(program: Vec<i64>) -> FlatComputer {
+        FlatComputer {
+            memory: program,
+            ip: 0,
+            relative_base: 0,
+        }
+    }
+
+    // Address of parameter `n` of the instruction at `ip`, growing memory to include it.
+    fn address(&mut self, n: usize) -> usize {
+        let mode = self.memory[self.ip] / [100, 1_000, 10_000][n - 1] % 10;
+        let value = self.memory[self.ip + n];
+        let address = match mode {
+            0 => value as usize,
+            1 => self.ip + n,
+            _ => (self.relative_base + value) as usize,
+        };
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+        address
+    }
+
+    fn read(&mut self, n: usize) -> i64 {
+        let address = self.address(n);
+        self.memory[address]
+    }
+}
+
+impl Droid for FlatComputer {
+    fn step(&mut self, direction: i64) -> i64 {
+        let mut input = Some(direction);
+        loop {
+            let ip = self.ip;
+            match self.memory[ip] % 100 {
+                opcode @ (1 | 2 | 7 | 8) => {
+                    let (a, b) = (self.read(1), self.read(2));
+                    let value = match opcode {
+                        1 => a + b,
+                        2 => a * b,
+                        7 => (a < b) as i64,
+                        _ => (a == b) as i64,
+                    };
+                    let target = self.address(3);
+                    self.memory[target] = value;
+                    self.ip += 4;
+                }
+                3 => {
+                    let target = self.address(1);
+                    self.memory[target] = input.take().expect("the droid wants more input");
+                    self.ip += 2;
+                }
+                4 => {
+                    let output = self.read(1);
+                    self.ip += 2;
+                    return output;
+                }
+                opcode @ (5 | 6) => {
+                    let (condition, target) = (self.read(1), self.read(2));
+                    self.ip = if (condition != 0) == (opcode == 5) {
+                        target as usize
+                    } else {
+                        ip + 3
+                    };
+                }
+                9 => {
+                    self.relative_base += self.read(1);
+                    self.ip += 2;
+                }
+                opcode => panic!("unexpected opcode {} at {}", opcode, ip),
+            }
+        }
+    }
+}
+
+// Visits every reachable tile depth first, with a clone of the computer for every move.
+fn explore<D: Droid>(droid: D, position: (i64, i64), map: &mut HashMap<(i64, i64), i64>) {
+    for (direction, (dx, dy)) in [(1, (0, 1)), (2, (0, -1)), (3, (-1, 0)), (4, (1, 0))] {
+        let next = (position.0 + dx, position.1 + dy);
+        if map.contains_key(&next) {
+            continue;
+        }
+        let mut moved = droid.clone();
+        let tile = moved.step(direction);
+        map.insert(next, tile);
+        if tile == 1 {
+            explore(moved, next, map);
+        }
+    }
+}
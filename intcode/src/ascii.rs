@@ -0,0 +1,269 @@
+use crate::{
+    computer::{Computer, ComputerState},
+    error::IntcodeError,
+    word::Word,
+};
+use std::{
+    error::Error,
+    fmt,
+    io::{self, BufRead, Write},
+    mem,
+};
+
+/// What an ASCII program produced next, see `AsciiComputer::read`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsciiOutput<W = i64> {
+    /// A complete line of text, without its newline.
+    Line(String),
+    /// The program wants input. Holds the text printed since the last complete line, which is
+    /// empty for programs that print their prompt on a line of its own.
+    Prompt(String),
+    /// A value outside the ASCII range, usually a puzzle answer too large to be a character.
+    Value(W),
+    Halt,
+}
+
+#[derive(Debug)]
+pub enum AsciiError<W = i64> {
+    Io(io::Error),
+    Intcode(IntcodeError<W>),
+}
+
+impl<W: Word> fmt::Display for AsciiError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsciiError::Io(err) => write!(f, "{}", err),
+            AsciiError::Intcode(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl<W: Word> Error for AsciiError<W> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AsciiError::Io(err) => Some(err),
+            AsciiError::Intcode(_) => None,
+        }
+    }
+}
+
+impl<W> From<io::Error> for AsciiError<W> {
+    fn from(err: io::Error) -> AsciiError<W> {
+        AsciiError::Io(err)
+    }
+}
+
+impl<W> From<IntcodeError<W>> for AsciiError<W> {
+    fn from(err: IntcodeError<W>) -> AsciiError<W> {
+        AsciiError::Intcode(err)
+    }
+}
+
+/// Wraps a `Computer` running a program that talks in lines of ASCII text.
+#[derive(Debug, Clone)]
+pub struct AsciiComputer<W = i64> {
+    pub computer: Computer<W>,
+    pending: String,
+    halted: bool,
+}
+
+impl<W: Word> AsciiComputer<W> {
+    pub fn new(computer: Computer<W>) -> AsciiComputer<W> {
+        AsciiComputer {
+            computer,
+            pending: String::new(),
+            halted: false,
+        }
+    }
+
+    /// Queues `line` followed by a newline as input.
+    ///
+    /// Panics if `line` contains anything but ASCII characters.
+    pub fn send_line(&mut self, line: &str) {
+        assert!(line.is_ascii(), "Cannot send non-ASCII line `{}`", line);
+        for c in line.bytes() {
+            self.computer.push_input(W::from_i64(i64::from(c)));
+        }
+        self.computer.push_input(W::from_i64(i64::from(b'\n')));
+    }
+
+    /// Runs the program until it completes a line, asks for input, outputs a non-ASCII value or
+    /// halts. Text left without a newline when the program halts is returned as a last `Line`.
+    pub fn read(&mut self) -> Result<AsciiOutput<W>, IntcodeError<W>> {
+        if self.halted {
+            return Ok(self.flush_or(AsciiOutput::Halt));
+        }
+        loop {
+            match self.computer.execute()? {
+                ComputerState::Output(value) => match as_ascii(&value) {
+                    Some('\n') => return Ok(AsciiOutput::Line(mem::take(&mut self.pending))),
+                    Some(c) => self.pending.push(c),
+                    None => return Ok(AsciiOutput::Value(value)),
+                },
+                ComputerState::InputRequired => {
+                    return Ok(AsciiOutput::Prompt(mem::take(&mut self.pending)))
+                }
+                ComputerState::Halt => {
+                    self.halted = true;
+                    return Ok(self.flush_or(AsciiOutput::Halt));
+                }
+                ComputerState::BudgetExhausted | ComputerState::LoopDetected { .. } => {
+                    unreachable!("execute runs without a budget")
+                }
+            }
+        }
+    }
+
+    /// Reads complete lines until the program asks for input or halts, returning the lines and
+    /// every non-ASCII value in between.
+    pub fn read_until_input(&mut self) -> Result<(Vec<String>, Vec<W>), IntcodeError<W>> {
+        let mut lines = Vec::new();
+        let mut values = Vec::new();
+        loop {
+            match self.read()? {
+                AsciiOutput::Line(line) => lines.push(line),
+                AsciiOutput::Value(value) => values.push(value),
+                AsciiOutput::Prompt(prompt) => {
+                    if !prompt.is_empty() {
+                        lines.push(prompt);
+                    }
+                    return Ok((lines, values));
+                }
+                AsciiOutput::Halt => return Ok((lines, values)),
+            }
+        }
+    }
+
+    /// Runs the program against a terminal: text goes to `output`, and every time the program
+    /// asks for input a line is read from `input` and sent. Non-ASCII values are written on a
+    /// line of their own. Returns once the program halts or `input` runs out.
+    pub fn interact<R: BufRead, O: Write>(
+        &mut self,
+        mut input: R,
+        mut output: O,
+    ) -> Result<(), AsciiError<W>> {
+        loop {
+            match self.read()? {
+                AsciiOutput::Line(line) => writeln!(output, "{}", line)?,
+                AsciiOutput::Value(value) => writeln!(output, "{}", value)?,
+                AsciiOutput::Prompt(prompt) => {
+                    write!(output, "{}", prompt)?;
+                    output.flush()?;
+                    let mut line = String::new();
+                    if input.read_line(&mut line)? == 0 {
+                        return Ok(());
+                    }
+                    let line = line.trim_end_matches(&['\n', '\r'][..]);
+                    if !line.is_ascii() {
+                        writeln!(output, "Only ASCII input is supported")?;
+                        continue;
+                    }
+                    self.send_line(line);
+                }
+                AsciiOutput::Halt => {
+                    output.flush()?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn flush_or(&mut self, output: AsciiOutput<W>) -> AsciiOutput<W> {
+        if self.pending.is_empty() {
+            output
+        } else {
+            AsciiOutput::Line(mem::take(&mut self.pending))
+        }
+    }
+}
+
+fn as_ascii<W: Word>(value: &W) -> Option<char> {
+    value
+        .to_i64()
+        .filter(|value| (0..128).contains(value))
+        .map(|value| value as u8 as char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble::assemble;
+
+    // Greets, then echoes a line of input and prints an answer and some unterminated text.
+    const ECHO: &str = "
+            OUT #72
+            OUT #105
+            OUT #10
+            OUT #63
+    loop:   IN -> [char]
+            OUT [char]
+            EQ [char], #10 -> [done]
+            JZ [done], #loop
+            OUT #1000
+            OUT #-1
+            OUT #111
+            OUT #107
+            HLT
+    char:   DATA 0
+    done:   DATA 0";
+
+    fn echo() -> AsciiComputer {
+        AsciiComputer::new(Computer::new(assemble(ECHO).unwrap()))
+    }
+
+    #[test]
+    fn splits_lines_prompts_and_values() {
+        let mut computer = echo();
+        assert_eq!(computer.read(), Ok(AsciiOutput::Line("Hi".to_owned())));
+        assert_eq!(computer.read(), Ok(AsciiOutput::Prompt("?".to_owned())));
+        assert_eq!(computer.read(), Ok(AsciiOutput::Prompt(String::new())));
+        computer.send_line("abc");
+        assert_eq!(computer.read(), Ok(AsciiOutput::Line("abc".to_owned())));
+        assert_eq!(computer.read(), Ok(AsciiOutput::Value(1000)));
+        assert_eq!(computer.read(), Ok(AsciiOutput::Value(-1)));
+        assert_eq!(computer.read(), Ok(AsciiOutput::Line("ok".to_owned())));
+        assert_eq!(computer.read(), Ok(AsciiOutput::Halt));
+        assert_eq!(computer.read(), Ok(AsciiOutput::Halt));
+    }
+
+    #[test]
+    fn reads_until_input() {
+        let mut computer = echo();
+        assert_eq!(
+            computer.read_until_input(),
+            Ok((vec!["Hi".to_owned(), "?".to_owned()], vec![]))
+        );
+        computer.send_line("");
+        assert_eq!(
+            computer.read_until_input(),
+            Ok((vec![String::new(), "ok".to_owned()], vec![1000, -1]))
+        );
+    }
+
+    #[test]
+    fn interacts_with_a_terminal() {
+        let mut computer = echo();
+        let mut output = Vec::new();
+        computer
+            .interact("\u{e9}t\u{e9}\nabc\n".as_bytes(), &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Hi\n?Only ASCII input is supported\nabc\n1000\n-1\nok\n"
+        );
+    }
+
+    #[test]
+    fn stops_interacting_when_input_runs_out() {
+        let mut computer = echo();
+        let mut output = Vec::new();
+        computer.interact(&b""[..], &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "Hi\n?");
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot send non-ASCII line")]
+    fn refuses_to_send_non_ascii() {
+        echo().send_line("\u{e9}");
+    }
+}
@@ -0,0 +1,452 @@
+use crate::{
+    computer::Program,
+    instruction::{Opcode, ParameterMode},
+};
+use std::{collections::HashMap, convert::TryFrom, error::Error, fmt};
+
+/// Error produced while assembling, with the 1-based source line it was found on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub kind: AssembleErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssembleErrorKind {
+    UnknownMnemonic(String),
+    OperandCount { expected: usize, found: usize },
+    InvalidOperand(String),
+    InvalidLabel(String),
+    DuplicateLabel(String),
+    UnknownLabel(String),
+    ImmediateWrite,
+    AddressOverflow,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            AssembleErrorKind::UnknownMnemonic(mnemonic) => {
+                write!(f, "unknown mnemonic `{}`", mnemonic)
+            }
+            AssembleErrorKind::OperandCount { expected, found } => {
+                write!(f, "expected {} operands, found {}", expected, found)
+            }
+            AssembleErrorKind::InvalidOperand(operand) => {
+                write!(f, "invalid operand `{}`", operand)
+            }
+            AssembleErrorKind::InvalidLabel(label) => write!(f, "invalid label `{}`", label),
+            AssembleErrorKind::DuplicateLabel(label) => write!(f, "duplicate label `{}`", label),
+            AssembleErrorKind::UnknownLabel(label) => write!(f, "unknown label `{}`", label),
+            AssembleErrorKind::ImmediateWrite => write!(f, "cannot write to an immediate operand"),
+            AssembleErrorKind::AddressOverflow => write!(f, "address out of range"),
+        }
+    }
+}
+
+impl Error for AssembleError {}
+
+// A number, or a label with an optional offset, resolved once every label has an address.
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(i64),
+    Label(String, i64),
+}
+
+#[derive(Debug, Clone)]
+struct Operand {
+    mode: ParameterMode,
+    expr: Expr,
+}
+
+#[derive(Debug)]
+enum Item {
+    Instruction(Opcode, Vec<Operand>),
+    Data(Vec<Expr>),
+}
+
+/// Assembles Intcode source into a program.
+///
+/// Each line holds an optional `label:`, then either an instruction or a `DATA` directive, and
+/// anything after a `;` is a comment. Mnemonics match the disassembler (`ADD`, `MUL`, `IN`, `OUT`,
+/// `JNZ`, `JZ`, `LT`, `EQ`, `ARB`, `HLT`) and are case-insensitive. Operands are written as `#5`
+/// for immediate, `[100]` for position and `[r+3]` for relative mode, and the write target may be
+/// separated with `->` instead of a comma:
+///
+/// ```text
+///         IN -> [count]
+/// loop:   OUT [count]
+///         ADD [count], #-1 -> [count]
+///         JNZ [count], #loop
+///         HLT
+/// count:  DATA 0
+/// ```
+///
+/// Numbers may be replaced by a label, optionally followed by `+n` or `-n`. A label can't be
+/// called `r`, which is the relative base.
+pub fn assemble(source: &str) -> Result<Program, AssembleError> {
+    let mut items = Vec::new();
+    let mut labels = HashMap::new();
+    let mut address: usize = 0;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |kind| AssembleError {
+            line: line_number,
+            kind,
+        };
+
+        let mut line = line.split(';').next().unwrap().trim();
+        if let Some(colon) = line.find(':') {
+            let label = line[..colon].trim();
+            // `r` on its own is the relative base, `[r]` could never refer to the label.
+            if !is_identifier(label) || label.eq_ignore_ascii_case("r") {
+                return Err(error(AssembleErrorKind::InvalidLabel(label.to_owned())));
+            }
+            if labels.insert(label.to_owned(), address).is_some() {
+                return Err(error(AssembleErrorKind::DuplicateLabel(label.to_owned())));
+            }
+            line = line[colon + 1..].trim();
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, rest) = match line.find(char::is_whitespace) {
+            Some(split) => (&line[..split], line[split..].trim()),
+            None => (line, ""),
+        };
+        let (reads, write) = match rest.find("->") {
+            Some(arrow) => (&rest[..arrow], Some(&rest[arrow + 2..])),
+            None => (rest, None),
+        };
+        let operands: Vec<String> = reads
+            .split(',')
+            .filter(|_| !reads.trim().is_empty())
+            .chain(write)
+            .map(|operand| operand.trim().to_owned())
+            .collect();
+
+        if mnemonic.eq_ignore_ascii_case("DATA") {
+            let values = operands
+                .iter()
+                .map(|operand| parse_expr(operand))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| error(AssembleErrorKind::InvalidOperand(rest.to_owned())))?;
+            address = address
+                .checked_add(values.len())
+                .ok_or_else(|| error(AssembleErrorKind::AddressOverflow))?;
+            items.push((line_number, Item::Data(values)));
+            continue;
+        }
+
+        let opcode = Opcode::ALL
+            .iter()
+            .copied()
+            .find(|opcode| opcode.mnemonic().eq_ignore_ascii_case(mnemonic))
+            .ok_or_else(|| error(AssembleErrorKind::UnknownMnemonic(mnemonic.to_owned())))?;
+        if operands.len() != opcode.parameter_count() {
+            return Err(error(AssembleErrorKind::OperandCount {
+                expected: opcode.parameter_count(),
+                found: operands.len(),
+            }));
+        }
+        let operands = operands
+            .iter()
+            .map(|operand| {
+                parse_operand(operand)
+                    .ok_or_else(|| error(AssembleErrorKind::InvalidOperand(operand.clone())))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if opcode.writes() && operands.last().unwrap().mode == ParameterMode::Immediate {
+            return Err(error(AssembleErrorKind::ImmediateWrite));
+        }
+
+        address = address
+            .checked_add(opcode.parameter_count() + 1)
+            .ok_or_else(|| error(AssembleErrorKind::AddressOverflow))?;
+        items.push((line_number, Item::Instruction(opcode, operands)));
+    }
+
+    let mut program = Vec::with_capacity(address);
+    for (line, item) in items {
+        let error = |kind| AssembleError { line, kind };
+        let resolve = |expr: &Expr| match expr {
+            Expr::Number(value) => Ok(*value),
+            Expr::Label(label, offset) => {
+                let address = labels
+                    .get(label)
+                    .ok_or_else(|| error(AssembleErrorKind::UnknownLabel(label.clone())))?;
+                i64::try_from(*address)
+                    .ok()
+                    .and_then(|address| address.checked_add(*offset))
+                    .ok_or_else(|| error(AssembleErrorKind::AddressOverflow))
+            }
+        };
+        match item {
+            Item::Instruction(opcode, operands) => {
+                let modes = operands
+                    .iter()
+                    .rev()
+                    .fold(0, |modes, operand| modes * 10 + operand.mode.as_int());
+                program.push(modes * 100 + opcode.as_int());
+                for operand in &operands {
+                    program.push(resolve(&operand.expr)?);
+                }
+            }
+            Item::Data(values) => {
+                for value in &values {
+                    program.push(resolve(value)?);
+                }
+            }
+        }
+    }
+    Ok(program)
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_operand(text: &str) -> Option<Operand> {
+    if let Some(value) = text.strip_prefix('#') {
+        return Some(Operand {
+            mode: ParameterMode::Immediate,
+            expr: parse_expr(value)?,
+        });
+    }
+
+    let inner = text.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let relative = inner
+        .strip_prefix('r')
+        .or_else(|| inner.strip_prefix('R'))
+        .map(str::trim)
+        .filter(|rest| rest.is_empty() || rest.starts_with('+') || rest.starts_with('-'));
+    match relative {
+        Some("") => Some(Operand {
+            mode: ParameterMode::Relative,
+            expr: Expr::Number(0),
+        }),
+        Some(offset) => {
+            let expr = match (offset.strip_prefix('-'), parse_expr(&offset[1..])?) {
+                (None, expr) => expr,
+                (Some(_), Expr::Number(value)) => Expr::Number(value.checked_neg()?),
+                (Some(_), Expr::Label(..)) => return None,
+            };
+            Some(Operand {
+                mode: ParameterMode::Relative,
+                expr,
+            })
+        }
+        None => Some(Operand {
+            mode: ParameterMode::Position,
+            expr: parse_expr(inner)?,
+        }),
+    }
+}
+
+fn parse_expr(text: &str) -> Option<Expr> {
+    let text = text.trim();
+    if let Ok(value) = text.parse() {
+        return Some(Expr::Number(value));
+    }
+
+    let split = text.find(['+', '-']).unwrap_or(text.len());
+    let (label, offset) = text.split_at(split);
+    let label = label.trim();
+    if !is_identifier(label) {
+        return None;
+    }
+    let offset = match offset.trim() {
+        "" => 0,
+        offset => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            offset[1..].trim().parse::<i64>().ok()?.checked_mul(sign)?
+        }
+    };
+    Some(Expr::Label(label.to_owned(), offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        computer::{Computer, ComputerState},
+        disassemble::{disassemble, label_name, Entry},
+    };
+
+    fn error(source: &str) -> String {
+        assemble(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn resolves_labels_and_forward_references() {
+        let program = assemble(
+            "        JZ #0, #start
+            value:  DATA 7
+            start:  OUT [value]
+                    OUT #end
+                    OUT #value+2
+                    OUT #end-1
+            end:    HLT",
+        )
+        .unwrap();
+        assert_eq!(
+            program,
+            vec![1106, 0, 4, 7, 4, 3, 104, 12, 104, 5, 104, 11, 99]
+        );
+    }
+
+    #[test]
+    fn data_holds_numbers_and_labels() {
+        let program = assemble(
+            "table: DATA 1, -2, table, end+1
+             end:   DATA 99",
+        )
+        .unwrap();
+        assert_eq!(program, vec![1, -2, 0, 5, 99]);
+    }
+
+    #[test]
+    fn encodes_parameter_modes() {
+        let program = assemble(
+            "ADD #1, [2] -> [r+3]
+             MUL [r-4], [r], [5]
+             LT [r + 6], #-7 -> [8]",
+        )
+        .unwrap();
+        assert_eq!(
+            program,
+            vec![20101, 1, 2, 3, 2202, -4, 0, 5, 1207, 6, -7, 8]
+        );
+    }
+
+    #[test]
+    fn mnemonics_are_case_insensitive() {
+        assert_eq!(
+            assemble("in -> [R+1]\nJnz [0], #0\narb #2\nData 5\nhlt"),
+            assemble("IN -> [r+1]\nJNZ [0], #0\nARB #2\nDATA 5\nHLT")
+        );
+    }
+
+    #[test]
+    fn assembled_programs_run() {
+        let program = assemble(
+            "        IN -> [count]
+            loop:   OUT [count]
+                    ADD [count], #-1 -> [count]
+                    JNZ [count], #loop
+                    HLT
+            count:  DATA 0",
+        )
+        .unwrap();
+        let mut computer = Computer::new(program);
+        computer.push_input(3);
+        let mut outputs = Vec::new();
+        while let ComputerState::Output(output) = computer.execute().unwrap() {
+            outputs.push(output);
+        }
+        assert_eq!(outputs, vec![3, 2, 1]);
+    }
+
+    // Turns a disassembly back into source the assembler accepts.
+    fn source(program: &[i64]) -> String {
+        let disassembly = disassemble(program);
+        let mut source = String::new();
+        for entry in &disassembly.entries {
+            let (address, text) = match entry {
+                Entry::Instruction {
+                    address,
+                    instruction,
+                } => (address, disassembly.format_instruction(instruction)),
+                Entry::Data { address, values } => {
+                    let values: Vec<String> = values.iter().map(i64::to_string).collect();
+                    (address, format!("DATA {}", values.join(", ")))
+                }
+            };
+            if disassembly.labels.contains(address) {
+                source.push_str(&format!("{}:", label_name(*address)));
+            }
+            source.push_str(&format!(" {}\n", text));
+        }
+        source
+    }
+
+    #[test]
+    fn round_trips_through_the_disassembler() {
+        let programs = [
+            "        IN -> [count]
+            loop:   OUT [count]
+                    ADD [count], #-1 -> [count]
+                    JNZ [count], #loop
+                    HLT
+            count:  DATA 0",
+            "        ARB #stack
+                    ADD #back, #0 -> [r]
+                    JNZ #1, #function
+            back:   HLT
+            function:
+                    MUL [r+1], #2 -> [r-1]
+                    JZ #0, [r]
+            stack:  DATA 0, 0, 0",
+        ];
+        for text in &programs {
+            let program = assemble(text).unwrap();
+            assert_eq!(assemble(&source(&program)).unwrap(), program);
+        }
+    }
+
+    #[test]
+    fn reports_errors_with_their_line() {
+        assert_eq!(
+            error("OUT #1\nJNZ #1, #nowhere"),
+            "line 2: unknown label `nowhere`"
+        );
+        assert_eq!(
+            error("ADD #1, #2 -> #3"),
+            "line 1: cannot write to an immediate operand"
+        );
+        assert_eq!(
+            error("IN #3"),
+            "line 1: cannot write to an immediate operand"
+        );
+        assert_eq!(error("\nNOP"), "line 2: unknown mnemonic `NOP`");
+        assert_eq!(error("OUT #1, #2"), "line 1: expected 1 operands, found 2");
+        assert_eq!(error("OUT 5"), "line 1: invalid operand `5`");
+        assert_eq!(error("a: HLT\na: HLT"), "line 2: duplicate label `a`");
+        assert_eq!(error("1a: HLT"), "line 1: invalid label `1a`");
+    }
+
+    #[test]
+    fn rejects_offsets_out_of_range() {
+        let max = i64::MAX;
+        assert_eq!(
+            assemble(&format!("DATA 1\na: DATA a+{}", max - 1)),
+            Ok(vec![1, max])
+        );
+        assert_eq!(
+            error(&format!("HLT\na: DATA 0\nOUT [a+{}]", max)),
+            "line 3: address out of range"
+        );
+        assert_eq!(
+            error(&format!("a: HLT\nDATA a-{}, a - {}", max, i64::MIN)),
+            "line 2: invalid operand `a-9223372036854775807, a - -9223372036854775808`"
+        );
+        assert_eq!(
+            error(&format!("OUT [r - {}]", i64::MIN)),
+            "line 1: invalid operand `[r - -9223372036854775808]`"
+        );
+    }
+
+    #[test]
+    fn rejects_r_as_a_label() {
+        assert_eq!(error("r: DATA 0"), "line 1: invalid label `r`");
+        assert_eq!(error("R: DATA 0"), "line 1: invalid label `R`");
+        assert_eq!(assemble("rest: OUT [rest]"), Ok(vec![4, 0]));
+    }
+}
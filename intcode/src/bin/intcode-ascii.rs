@@ -0,0 +1,11 @@
+use intcode::{cli, AsciiComputer, Computer};
+use std::io::{stdin, stdout};
+
+fn main() {
+    let program = cli::read_program_arg("intcode-ascii <program>");
+    let mut ascii = AsciiComputer::new(Computer::new(program));
+    let stdin = stdin();
+    if let Err(err) = ascii.interact(stdin.lock(), stdout()) {
+        cli::fail(format_args!("Stopped: {}", err));
+    }
+}
@@ -0,0 +1,15 @@
+use intcode::{assemble, cli};
+
+fn main() {
+    let path = cli::path_arg("intcode-asm <source>");
+    match assemble(&cli::read_file(&path)) {
+        Ok(program) => {
+            let words = program
+                .iter()
+                .map(|word| word.to_string())
+                .collect::<Vec<_>>();
+            println!("{}", words.join(","));
+        }
+        Err(err) => cli::fail(format_args!("{}: {}", path, err)),
+    }
+}
@@ -0,0 +1,10 @@
+use intcode::{cli, control_flow_graph};
+use std::io::stdout;
+
+fn main() {
+    let program = cli::read_program_arg("intcode-cfg <program>");
+    let stdout = stdout();
+    if let Err(err) = control_flow_graph(&program).write_dot(stdout.lock()) {
+        cli::fail(format_args!("Could not write graph: {}", err));
+    }
+}
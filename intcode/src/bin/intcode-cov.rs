@@ -0,0 +1,54 @@
+use intcode::{cli, disassemble, Computer, ComputerState, Coverage};
+use std::{
+    fs::{self, File},
+    io::{stdout, BufWriter},
+};
+
+const USAGE: &str = "intcode-cov [--lcov <file>] <program> [input...]";
+
+fn main() {
+    let mut args = cli::args();
+    let lcov = cli::take_option(&mut args, "--lcov");
+    if args.is_empty() {
+        cli::usage(USAGE);
+    }
+
+    let program = cli::read_program(&args[0]);
+    let mut computer = Computer::new(program.clone());
+    cli::push_inputs(&mut computer, &args[1..]);
+
+    let mut coverage = Coverage::new();
+    let mut outputs = Vec::new();
+    loop {
+        match computer.execute_with(&mut coverage) {
+            Ok(ComputerState::Output(output)) => outputs.push(output.to_string()),
+            Ok(ComputerState::Halt) => break,
+            Ok(state) => {
+                eprintln!("Stopped at ip {}: {:?}", computer.ip(), state);
+                break;
+            }
+            Err(err) => {
+                eprintln!("Stopped: {}", err);
+                break;
+            }
+        }
+    }
+
+    println!("Outputs: {}", outputs.join(","));
+    println!();
+    let stdout = stdout();
+    if let Err(err) = coverage.write_report(&program, stdout.lock()) {
+        cli::fail(format_args!("Could not write report: {}", err));
+    }
+    // The tracefile refers to lines of the listing, which is written next to it.
+    if let Some(path) = lcov {
+        let listing = format!("{}.dis", path);
+        let written = fs::write(&listing, disassemble(&program).to_string()).and_then(|_| {
+            File::create(&path)
+                .and_then(|file| coverage.write_lcov(&program, &listing, BufWriter::new(file)))
+        });
+        if let Err(err) = written {
+            cli::fail(format_args!("Could not write {}: {}", path, err));
+        }
+    }
+}
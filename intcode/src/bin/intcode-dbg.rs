@@ -0,0 +1,263 @@
+use intcode::{cli, Computer, Debugger, Instruction, StopReason};
+use std::{
+    fs::File,
+    io::{stdin, stdout, BufRead, BufReader, BufWriter, Write},
+};
+
+const HELP: &str = "\
+Commands:
+  s, step [n]            execute n instructions (default 1)
+  c, continue            run until a breakpoint, watchpoint, input request or halt
+  o, next-output         like continue, but also stop after the next output
+  i, next-input          run until input is required, ignoring breakpoints and watchpoints
+  rs, reverse-step [n]   undo n instructions (default 1)
+  rw, reverse-write <addr>
+                         step back to just before the last write to addr
+  history <n>            remember the last n instructions for reverse stepping
+  b, break <addr>        set a breakpoint on ip
+  d, delete <addr>       remove a breakpoint
+  w, watch <addr|rb>     stop when a memory address or the relative base changes
+  unwatch <addr|rb>      remove a watchpoint
+  info                   show ip, relative base, breakpoints, watchpoints and queued input
+  x <addr> [n]           show n memory words starting at addr (default 1)
+  set <addr> <value>     write a value to memory
+  dis [addr] [n]         disassemble n instructions starting at addr (default ip, 10)
+  input <value>...       queue input values
+  clear-input            drop all queued input
+  save <file>            write a snapshot of the computer to file
+  load <file>            replace the computer with a snapshot read from file
+  q, quit                leave the debugger";
+
+// Instructions remembered for reverse stepping unless changed with `history`.
+const HISTORY_LIMIT: usize = 100_000;
+
+fn main() {
+    let program = cli::read_program_arg("intcode-dbg <program>");
+    let mut debugger = Debugger::new(Computer::new(program));
+    debugger.set_history_limit(HISTORY_LIMIT);
+
+    println!("Type `help` for a list of commands.");
+    show_current(&debugger);
+    let stdin = stdin();
+    loop {
+        print!("(dbg) ");
+        stdout().flush().expect("Failed to flush stdout");
+        let mut line = String::new();
+        if stdin
+            .lock()
+            .read_line(&mut line)
+            .expect("Could not read input")
+            == 0
+        {
+            break;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        if words[0] == "q" || words[0] == "quit" {
+            break;
+        }
+        if let Err(message) = run_command(&mut debugger, &words) {
+            println!("{}", message);
+        }
+    }
+}
+
+fn run_command(debugger: &mut Debugger, words: &[&str]) -> Result<(), String> {
+    let args = &words[1..];
+    match words[0] {
+        "help" | "h" => println!("{}", HELP),
+        "s" | "step" => {
+            let count = optional_arg(args, 0, 1)?;
+            let reason = debugger.step(count);
+            stopped(debugger, reason);
+        }
+        "c" | "continue" => {
+            let reason = debugger.resume();
+            stopped(debugger, reason);
+        }
+        "o" | "next-output" => {
+            let reason = debugger.run_to_output();
+            stopped(debugger, reason);
+        }
+        "i" | "next-input" => {
+            let reason = debugger.run_to_input();
+            stopped(debugger, reason);
+        }
+        "rs" | "reverse-step" => {
+            let count = optional_arg(args, 0, 1)?;
+            let undone = debugger.step_back(count);
+            if undone < count {
+                println!("Reached the start of the history after {} steps", undone);
+            }
+            show_current(debugger);
+        }
+        "rw" | "reverse-write" => {
+            let address = required_arg(args, 0)?;
+            match debugger.run_back_to_write(address) {
+                Some(undone) => println!("Stepped back {} instructions", undone),
+                None => return Err("No write to that address in the history".to_owned()),
+            }
+            show_current(debugger);
+        }
+        "history" => debugger.set_history_limit(required_arg(args, 0)?),
+        "b" | "break" => debugger.add_breakpoint(required_arg(args, 0)?),
+        "d" | "delete" => {
+            if !debugger.remove_breakpoint(required_arg(args, 0)?) {
+                return Err("No breakpoint at that address".to_owned());
+            }
+        }
+        "w" | "watch" if args.first() == Some(&"rb") => debugger.set_watch_relative_base(true),
+        "w" | "watch" => debugger.add_watchpoint(required_arg(args, 0)?),
+        "unwatch" if args.first() == Some(&"rb") => debugger.set_watch_relative_base(false),
+        "unwatch" => {
+            if !debugger.remove_watchpoint(required_arg(args, 0)?) {
+                return Err("No watchpoint on that address".to_owned());
+            }
+        }
+        "info" => {
+            let computer = &debugger.computer;
+            println!("ip: {}", computer.ip());
+            println!("relative base: {}", computer.relative_base());
+            println!("steps: {}", debugger.steps());
+            println!("history: {} steps", debugger.history_len());
+            println!(
+                "breakpoints: {:?}",
+                debugger.breakpoints().collect::<Vec<_>>()
+            );
+            println!(
+                "watchpoints: {:?}",
+                debugger.watchpoints().collect::<Vec<_>>()
+            );
+            println!(
+                "watching relative base: {}",
+                debugger.watches_relative_base()
+            );
+            println!(
+                "queued input: {:?}",
+                computer.inputs.iter().rev().collect::<Vec<_>>()
+            );
+        }
+        "x" => {
+            let address: usize = required_arg(args, 0)?;
+            let count: usize = optional_arg(args, 1, 1)?;
+            let end = address
+                .checked_add(count)
+                .ok_or_else(|| "Range goes past the end of memory".to_owned())?;
+            for address in address..end {
+                println!(
+                    "{:>5}: {}",
+                    address,
+                    debugger.computer.memory().get(address)
+                );
+            }
+        }
+        "set" => {
+            let address = required_arg(args, 0)?;
+            let value = required_arg(args, 1)?;
+            debugger.set_memory(address, value);
+        }
+        "dis" => {
+            let mut address = optional_arg(args, 0, debugger.computer.ip())?;
+            let count = optional_arg(args, 1, 10)?;
+            for _ in 0..count {
+                // Decoding reads the parameters after `address` as well.
+                if address.checked_add(4).is_none() {
+                    return Err("Reached the end of memory".to_owned());
+                }
+                let memory = debugger.computer.memory();
+                match Instruction::decode(address, |a| memory.get(a)) {
+                    Ok(instruction) => {
+                        println!("{:>5}  {}", address, instruction);
+                        address += instruction.size();
+                    }
+                    Err(_) => {
+                        println!("{:>5}  DATA {}", address, memory.get(address));
+                        address += 1;
+                    }
+                }
+            }
+        }
+        "input" => {
+            if args.is_empty() {
+                return Err("Expected at least one value".to_owned());
+            }
+            let values = (0..args.len())
+                .map(|index| required_arg(args, index))
+                .collect::<Result<Vec<i64>, _>>()?;
+            for value in values {
+                debugger.computer.push_input(value);
+            }
+        }
+        "clear-input" => debugger.computer.inputs.clear(),
+        "save" => {
+            let path: String = required_arg(args, 0)?;
+            let file =
+                File::create(&path).map_err(|err| format!("Could not create {}: {}", path, err))?;
+            debugger
+                .computer
+                .save(BufWriter::new(file))
+                .map_err(|err| format!("Could not write {}: {}", path, err))?;
+        }
+        "load" => {
+            let path: String = required_arg(args, 0)?;
+            let file =
+                File::open(&path).map_err(|err| format!("Could not open {}: {}", path, err))?;
+            let computer = Computer::load(BufReader::new(file))
+                .map_err(|err| format!("Could not load {}: {}", path, err))?;
+            debugger.replace_computer(computer);
+            show_current(debugger);
+        }
+        command => return Err(format!("Unknown command `{}`, try `help`", command)),
+    }
+    Ok(())
+}
+
+fn required_arg<T: std::str::FromStr>(args: &[&str], index: usize) -> Result<T, String> {
+    let arg = args
+        .get(index)
+        .ok_or_else(|| format!("Missing argument {}", index + 1))?;
+    arg.parse()
+        .map_err(|_| format!("Invalid argument `{}`", arg))
+}
+
+fn optional_arg<T: std::str::FromStr>(
+    args: &[&str],
+    index: usize,
+    default: T,
+) -> Result<T, String> {
+    if index < args.len() {
+        required_arg(args, index)
+    } else {
+        Ok(default)
+    }
+}
+
+fn stopped(debugger: &mut Debugger, reason: StopReason) {
+    for output in debugger.take_outputs() {
+        println!("Output: {}", output);
+    }
+    match reason {
+        StopReason::Stepped | StopReason::Output(_) => {}
+        StopReason::Breakpoint(ip) => println!("Breakpoint at {}", ip),
+        StopReason::Watchpoint { address, old, new } => {
+            println!("Watchpoint: [{}] changed from {} to {}", address, old, new)
+        }
+        StopReason::RelativeBaseChanged { old, new } => {
+            println!("Watchpoint: relative base changed from {} to {}", old, new)
+        }
+        StopReason::InputRequired => println!("Input required"),
+        StopReason::Halt => println!("Program halted"),
+        StopReason::Error(err) => println!("Error: {}", err),
+    }
+    show_current(debugger);
+}
+
+fn show_current(debugger: &Debugger) {
+    let computer = &debugger.computer;
+    match computer.current_instruction() {
+        Ok(instruction) => println!("{:>5}  {}", computer.ip(), instruction),
+        Err(err) => println!("{:>5}  {}", computer.ip(), err),
+    }
+}
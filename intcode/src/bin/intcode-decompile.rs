@@ -0,0 +1,6 @@
+use intcode::{cli, decompile};
+
+fn main() {
+    let program = cli::read_program_arg("intcode-decompile <program>");
+    print!("{}", decompile(&program));
+}
@@ -0,0 +1,6 @@
+use intcode::{cli, disassemble};
+
+fn main() {
+    let program = cli::read_program_arg("intcode-dis <program>");
+    print!("{}", disassemble(&program));
+}
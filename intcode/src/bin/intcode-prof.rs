@@ -0,0 +1,52 @@
+use intcode::{cli, Computer, ComputerState, Profiler};
+use std::{
+    fs::File,
+    io::{stdout, BufWriter},
+};
+
+const USAGE: &str = "intcode-prof [--folded <file>] <program> [input...]";
+
+// Number of addresses and blocks listed in the report.
+const TOP: usize = 20;
+
+fn main() {
+    let mut args = cli::args();
+    let folded = cli::take_option(&mut args, "--folded");
+    if args.is_empty() {
+        cli::usage(USAGE);
+    }
+
+    let mut computer = Computer::new(cli::read_program(&args[0]));
+    cli::push_inputs(&mut computer, &args[1..]);
+
+    let mut profiler = Profiler::new();
+    let mut outputs = Vec::new();
+    loop {
+        match computer.execute_with(&mut profiler) {
+            Ok(ComputerState::Output(output)) => outputs.push(output.to_string()),
+            Ok(ComputerState::Halt) => break,
+            Ok(state) => {
+                eprintln!("Stopped at ip {}: {:?}", computer.ip(), state);
+                break;
+            }
+            Err(err) => {
+                eprintln!("Stopped: {}", err);
+                break;
+            }
+        }
+    }
+
+    println!("Outputs: {}", outputs.join(","));
+    println!();
+    let stdout = stdout();
+    if let Err(err) = profiler.write_report(stdout.lock(), TOP) {
+        cli::fail(format_args!("Could not write report: {}", err));
+    }
+    if let Some(path) = folded {
+        let written =
+            File::create(&path).and_then(|file| profiler.write_folded(BufWriter::new(file)));
+        if let Err(err) = written {
+            cli::fail(format_args!("Could not write {}: {}", path, err));
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use intcode::{cli, CodeWriteDetector, Computer, ComputerState};
+use std::io::stdout;
+
+fn main() {
+    let args = cli::args();
+    if args.is_empty() {
+        cli::usage("intcode-smc <program> [input...]");
+    }
+    let mut computer = Computer::new(cli::read_program(&args[0]));
+    cli::push_inputs(&mut computer, &args[1..]);
+
+    let mut detector = CodeWriteDetector::new();
+    loop {
+        match computer.execute_with(&mut detector) {
+            Ok(ComputerState::Output(_)) => {}
+            Ok(ComputerState::Halt) => break,
+            Ok(state) => {
+                eprintln!("Stopped at ip {}: {:?}", computer.ip(), state);
+                break;
+            }
+            Err(err) => {
+                eprintln!("Stopped: {}", err);
+                break;
+            }
+        }
+    }
+
+    if detector.is_empty() {
+        println!("No writes into code");
+    } else if let Err(err) = detector.write_report(stdout().lock()) {
+        cli::fail(format_args!("Could not write report: {}", err));
+    }
+}
@@ -0,0 +1,64 @@
+use intcode::{cli, first_divergence, Computer, ComputerState, Tracer};
+use std::{
+    io::{stdout, BufReader, BufWriter},
+    process,
+};
+
+const USAGE: &str = "\
+intcode-trace run <program> [input...]
+       intcode-trace diff <trace> <trace>";
+
+fn main() {
+    let args = cli::args();
+    match args.first().map(String::as_str) {
+        Some("run") if args.len() >= 2 => run(&args[1], &args[2..]),
+        Some("diff") if args.len() == 3 => diff(&args[1], &args[2]),
+        _ => cli::usage(USAGE),
+    }
+}
+
+fn run(path: &str, inputs: &[String]) {
+    let mut computer = Computer::new(cli::read_program(path));
+    cli::push_inputs(&mut computer, inputs);
+
+    let stdout = stdout();
+    let mut tracer = Tracer::new(BufWriter::new(stdout.lock()));
+    loop {
+        match computer.execute_with(&mut tracer) {
+            Ok(ComputerState::Output(_)) => {}
+            Ok(ComputerState::Halt) => break,
+            Ok(ComputerState::InputRequired) => {
+                eprintln!("Stopped at ip {}: input required", computer.ip());
+                break;
+            }
+            Ok(state) => {
+                eprintln!("Stopped at ip {}: {:?}", computer.ip(), state);
+                break;
+            }
+            Err(err) => {
+                eprintln!("Stopped: {}", err);
+                break;
+            }
+        }
+    }
+    if let Err(err) = tracer.finish() {
+        cli::fail(format_args!("Could not write trace: {}", err));
+    }
+}
+
+fn diff(left: &str, right: &str) {
+    let open = |path: &str| BufReader::new(cli::open_file(path));
+    match first_divergence(open(left), open(right)) {
+        Ok(None) => println!("Traces are identical"),
+        Ok(Some(divergence)) => {
+            let show = |line: Option<String>| line.unwrap_or_else(|| "<end of trace>".to_owned());
+            println!("Traces diverge at line {}:", divergence.line);
+            println!("< {}", show(divergence.left));
+            println!("> {}", show(divergence.right));
+            process::exit(cli::FAILURE);
+        }
+        Err(err) => {
+            cli::fail(format_args!("Could not read traces: {}", err));
+        }
+    }
+}
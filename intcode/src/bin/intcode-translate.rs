@@ -0,0 +1,6 @@
+use intcode::{cli, translate};
+
+fn main() {
+    let program = cli::read_program_arg("intcode-translate <program>");
+    print!("{}", translate(&program));
+}
@@ -0,0 +1,223 @@
+use crate::{
+    computer::{Computer, ComputerState, StepResult},
+    error::IntcodeError,
+    memory::Memory,
+    observer::{StepEvent, StepObserver},
+    word::Word,
+};
+use std::{collections::hash_map::DefaultHasher, hash::Hasher};
+
+impl<W: Word> Computer<W> {
+    /// Like `execute`, but gives up with `ComputerState::BudgetExhausted` after `max_steps`
+    /// instructions. The computer can be resumed with another call.
+    pub fn execute_with_budget(
+        &mut self,
+        max_steps: u64,
+    ) -> Result<ComputerState<W>, IntcodeError<W>> {
+        for _ in 0..max_steps {
+            if let Some(state) = finished(self.step()?) {
+                return Ok(state);
+            }
+        }
+        Ok(ComputerState::BudgetExhausted)
+    }
+
+    /// Like `execute_with_budget`, but also stops with `ComputerState::LoopDetected` as soon as
+    /// the computer repeats a previous state exactly: same `ip`, relative base, memory and queued
+    /// input. Uses Brent's cycle detection, so a loop is found within about twice the steps it
+    /// takes to enter and go once around it, at the cost of one extra copy of the computer.
+    pub fn execute_detecting_loops(
+        &mut self,
+        max_steps: u64,
+    ) -> Result<ComputerState<W>, IntcodeError<W>> {
+        let mut detector = LoopDetector::new(self);
+        for _ in 0..max_steps {
+            if let Some(state) = finished(self.step_with(&mut detector)?) {
+                return Ok(state);
+            }
+            if let Some(period) = detector.check(self) {
+                return Ok(ComputerState::LoopDetected { period });
+            }
+        }
+        Ok(ComputerState::BudgetExhausted)
+    }
+
+    fn same_state(&self, other: &Computer<W>) -> bool {
+        self.ip() == other.ip()
+            && self.relative_base() == other.relative_base()
+            && self.inputs == other.inputs
+            && self.memory() == other.memory()
+    }
+}
+
+fn finished<W>(result: StepResult<W>) -> Option<ComputerState<W>> {
+    match result {
+        StepResult::Stop => Some(ComputerState::Halt),
+        StepResult::Output(output) => Some(ComputerState::Output(output)),
+        StepResult::InputRequired => Some(ComputerState::InputRequired),
+        StepResult::Continue => None,
+    }
+}
+
+// Keeps a hash of memory up to date from the step events, so most states can be told apart from
+// the saved one without comparing memory word by word.
+struct LoopDetector<W> {
+    memory_hash: u64,
+    saved: Computer<W>,
+    saved_hash: u64,
+    power: u64,
+    steps_since_save: u64,
+}
+
+impl<W: Word> LoopDetector<W> {
+    fn new(computer: &Computer<W>) -> LoopDetector<W> {
+        let memory_hash = memory_hash(computer.memory());
+        LoopDetector {
+            memory_hash,
+            saved: computer.clone(),
+            saved_hash: memory_hash,
+            power: 1,
+            steps_since_save: 0,
+        }
+    }
+
+    // Returns the loop period once `computer` is back in the saved state.
+    fn check(&mut self, computer: &Computer<W>) -> Option<u64> {
+        self.steps_since_save += 1;
+        if self.memory_hash == self.saved_hash
+            && computer.ip() == self.saved.ip()
+            && computer.same_state(&self.saved)
+        {
+            return Some(self.steps_since_save);
+        }
+        if self.steps_since_save == self.power {
+            self.saved = computer.clone();
+            self.saved_hash = self.memory_hash;
+            self.power *= 2;
+            self.steps_since_save = 0;
+        }
+        None
+    }
+}
+
+impl<W: Word> StepObserver<W> for LoopDetector<W> {
+    fn on_step(&mut self, event: &StepEvent<W>) {
+        if let Some(write) = &event.write {
+            self.memory_hash = self
+                .memory_hash
+                .wrapping_sub(word_hash(write.address, &write.old))
+                .wrapping_add(word_hash(write.address, &write.new));
+        }
+    }
+}
+
+// Sum of the hashes of every word, so a single write can be swapped in and out. Zero words don't
+// contribute, which keeps the hash independent of how far memory has grown.
+fn memory_hash<W: Word>(memory: &Memory<W>) -> u64 {
+    memory.nonzero_words().fold(0, |hash, (address, value)| {
+        hash.wrapping_add(word_hash(address, value))
+    })
+}
+
+fn word_hash<W: Word>(address: usize, value: &W) -> u64 {
+    if value.is_zero() {
+        return 0;
+    }
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    // splitmix64 finalizer
+    let mut x = (address as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ hasher.finish();
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts [14] up to 3 in a loop of three instructions, then outputs it.
+    const COUNT_TO_THREE: [i64; 16] = [
+        1001, 14, 1, 14, 1007, 14, 3, 15, 1005, 15, 0, 4, 14, 99, 0, 0,
+    ];
+
+    fn start(program: &[i64]) -> Computer {
+        Computer::new(program.to_vec())
+    }
+
+    #[test]
+    fn budget_runs_out_and_resumes() {
+        let mut computer = start(&COUNT_TO_THREE);
+        assert_eq!(
+            computer.execute_with_budget(5),
+            Ok(ComputerState::BudgetExhausted)
+        );
+        assert_eq!(computer.ip(), 8);
+        assert_eq!(computer.memory().get(14), 2);
+        assert_eq!(
+            computer.execute_with_budget(4),
+            Ok(ComputerState::BudgetExhausted)
+        );
+        assert_eq!(
+            computer.execute_with_budget(1),
+            Ok(ComputerState::Output(3))
+        );
+        assert_eq!(
+            computer.execute_with_budget(0),
+            Ok(ComputerState::BudgetExhausted)
+        );
+        assert_eq!(computer.execute_with_budget(1), Ok(ComputerState::Halt));
+    }
+
+    #[test]
+    fn finds_loops_with_their_period() {
+        // JNZ #1, #0
+        let mut computer = start(&[1105, 1, 0]);
+        assert_eq!(
+            computer.execute_detecting_loops(100),
+            Ok(ComputerState::LoopDetected { period: 1 })
+        );
+
+        // Stores constants, then jumps back, which doesn't change anything after the first time.
+        let mut computer = start(&[1101, 2, 3, 11, 1101, 4, 5, 12, 1105, 1, 4, 0, 0]);
+        assert_eq!(
+            computer.execute_detecting_loops(100),
+            Ok(ComputerState::LoopDetected { period: 2 })
+        );
+
+        // Flips the sign of [7] each time round, so the state repeats every other iteration.
+        let mut computer = start(&[1002, 7, -1, 7, 1105, 1, 0, 5]);
+        assert_eq!(
+            computer.execute_detecting_loops(100),
+            Ok(ComputerState::LoopDetected { period: 4 })
+        );
+    }
+
+    #[test]
+    fn loops_that_change_memory_are_not_loops() {
+        // Counts [7] up forever.
+        let mut computer = start(&[1001, 7, 1, 7, 1105, 1, 0, 0]);
+        assert_eq!(
+            computer.execute_detecting_loops(10_000),
+            Ok(ComputerState::BudgetExhausted)
+        );
+        assert_eq!(computer.memory().get(7), 5_000);
+    }
+
+    #[test]
+    fn loop_detection_stops_at_output_and_input() {
+        let mut computer = start(&COUNT_TO_THREE);
+        assert_eq!(
+            computer.execute_detecting_loops(100),
+            Ok(ComputerState::Output(3))
+        );
+
+        // Waits for input in a loop that only ends once input arrives.
+        let mut computer = start(&[3, 5, 1105, 1, 0, 0]);
+        computer.push_input(1);
+        assert_eq!(
+            computer.execute_detecting_loops(100),
+            Ok(ComputerState::InputRequired)
+        );
+    }
+}
@@ -0,0 +1,356 @@
+use crate::{
+    disassemble::{
+        disassemble, jump_outcomes, jump_target, label_name, return_address, Disassembly, Entry,
+    },
+    instruction::{Instruction, Opcode, ParameterMode},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    io::{self, Write},
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Execution runs on into the next block, also when a conditional jump isn't taken.
+    Fallthrough,
+    Jump,
+    /// From a block ending in a call to the return address the call pushed. The callee gets back
+    /// there with an unresolved jump.
+    Return,
+}
+
+/// A transfer of control to `target`. A jump's target is the start of a block unless the jump
+/// lands inside another instruction or on something that doesn't decode as one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub target: usize,
+    pub kind: EdgeKind,
+}
+
+/// A run of instructions only entered at its first one and left after its last one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub instructions: Vec<(usize, Instruction)>,
+    pub successors: Vec<Edge>,
+    /// Whether the block ends in a jump whose target is read from memory, so it can't be known
+    /// without running the program.
+    pub unresolved: bool,
+}
+
+impl BasicBlock {
+    /// The address right after the last instruction.
+    pub fn end(&self) -> usize {
+        self.instructions
+            .last()
+            .map_or(self.start, |(address, instruction)| {
+                address + instruction.size()
+            })
+    }
+}
+
+/// The basic blocks of a program and the control flow between them.
+///
+/// Code is found the same way `disassemble` finds it: by following jumps with immediate targets
+/// from address 0, plus the return addresses pushed right before a call. Blocks end at jumps and
+/// halts, right before an address that is jumped to, and where code runs into data.
+#[derive(Debug, Clone)]
+pub struct ControlFlowGraph {
+    pub blocks: BTreeMap<usize, BasicBlock>,
+    disassembly: Disassembly,
+}
+
+pub fn control_flow_graph(program: &[i64]) -> ControlFlowGraph {
+    let disassembly = disassemble(program);
+    let code: BTreeMap<usize, Instruction> = disassembly
+        .entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Entry::Instruction {
+                address,
+                instruction,
+            } => Some((*address, *instruction)),
+            Entry::Data { .. } => None,
+        })
+        .collect();
+
+    let mut leaders: BTreeSet<usize> = disassembly
+        .labels
+        .iter()
+        .copied()
+        .filter(|address| code.contains_key(address))
+        .collect();
+    let mut expected = None;
+    for (address, instruction) in &code {
+        if expected != Some(*address) {
+            leaders.insert(*address);
+        }
+        let end = address + instruction.size();
+        if ends_block(instruction) {
+            leaders.insert(end);
+        }
+        expected = Some(end);
+    }
+
+    let mut blocks = BTreeMap::new();
+    for &start in leaders.iter().filter(|address| code.contains_key(address)) {
+        let mut block = BasicBlock {
+            start,
+            instructions: Vec::new(),
+            successors: Vec::new(),
+            unresolved: false,
+        };
+        let mut address = start;
+        while let Some(instruction) = code.get(&address) {
+            block.instructions.push((address, *instruction));
+            address += instruction.size();
+            if ends_block(instruction) || leaders.contains(&address) {
+                break;
+            }
+        }
+
+        let (_, last) = block.instructions[block.instructions.len() - 1];
+        let end = block.end();
+        let mut falls_through = last.opcode != Opcode::Halt;
+        if last.opcode.is_jump() {
+            let (taken, falls) = jump_outcomes(&last);
+            falls_through = falls;
+            if taken {
+                match jump_target(&last) {
+                    Some(target) => block.successors.push(Edge {
+                        target,
+                        kind: EdgeKind::Jump,
+                    }),
+                    None => block.unresolved = true,
+                }
+            }
+            let pushes_return = block
+                .instructions
+                .iter()
+                .any(|(_, instruction)| return_address(instruction) == Some(end));
+            if !falls && pushes_return && code.contains_key(&end) {
+                block.successors.push(Edge {
+                    target: end,
+                    kind: EdgeKind::Return,
+                });
+            }
+        }
+        if falls_through && code.contains_key(&end) {
+            block.successors.push(Edge {
+                target: end,
+                kind: EdgeKind::Fallthrough,
+            });
+        }
+        blocks.insert(start, block);
+    }
+
+    ControlFlowGraph {
+        blocks,
+        disassembly,
+    }
+}
+
+fn ends_block(instruction: &Instruction) -> bool {
+    instruction.opcode.is_jump() || instruction.opcode == Opcode::Halt
+}
+
+impl ControlFlowGraph {
+    // Parameter words of found instructions that the program writes to with a constant address,
+    // the way programs index arrays or call through a function pointer. What such a parameter
+    // holds when the instruction runs isn't known without running the program.
+    pub(crate) fn patched_parameters(&self) -> BTreeSet<usize> {
+        let instructions = || self.blocks.values().flat_map(|block| &block.instructions);
+        let mut code = BTreeSet::new();
+        let mut opcodes = BTreeSet::new();
+        for (address, instruction) in instructions() {
+            code.extend(*address..address + instruction.size());
+            opcodes.insert(*address);
+        }
+        instructions()
+            .filter_map(|(_, instruction)| {
+                let target = instruction.parameters().last()?;
+                let address = usize::try_from(target.value).ok()?;
+                let constant =
+                    instruction.opcode.writes() && target.mode != ParameterMode::Relative;
+                let parameter = code.contains(&address) && !opcodes.contains(&address);
+                Some(address).filter(|_| constant && parameter)
+            })
+            .collect()
+    }
+
+    /// Starts of the blocks with an edge to the block starting at `start`.
+    pub fn predecessors(&self, start: usize) -> impl Iterator<Item = usize> + '_ {
+        self.blocks
+            .values()
+            .filter(move |block| block.successors.iter().any(|edge| edge.target == start))
+            .map(|block| block.start)
+    }
+
+    /// Writes the graph in Graphviz DOT format, one node per block named by its label. Jumps
+    /// are solid, fallthroughs plain and call returns dotted; unresolved jumps lead to a `?`
+    /// node, and jumps to an address that doesn't start a block to a dashed node of their own.
+    pub fn write_dot<O: Write>(&self, mut writer: O) -> io::Result<()> {
+        writeln!(writer, "digraph cfg {{")?;
+        writeln!(writer, "    node [shape=box, fontname=\"monospace\"];")?;
+        for block in self.blocks.values() {
+            let mut label = format!("{}:\\l", label_name(block.start));
+            for (address, instruction) in &block.instructions {
+                label.push_str(&format!(
+                    "{:>5}  {}\\l",
+                    address,
+                    self.disassembly.format_instruction(instruction)
+                ));
+            }
+            writeln!(
+                writer,
+                "    {} [label=\"{}\"];",
+                label_name(block.start),
+                label
+            )?;
+        }
+        let mut unresolved = false;
+        for block in self.blocks.values() {
+            for edge in &block.successors {
+                let style = match edge.kind {
+                    EdgeKind::Jump => "label=\"jump\"",
+                    EdgeKind::Fallthrough => "color=gray40",
+                    EdgeKind::Return => "style=dotted, label=\"return\"",
+                };
+                writeln!(
+                    writer,
+                    "    {} -> {} [{}];",
+                    label_name(block.start),
+                    label_name(edge.target),
+                    style
+                )?;
+            }
+            if block.unresolved {
+                unresolved = true;
+                writeln!(
+                    writer,
+                    "    {} -> unresolved [style=dashed];",
+                    label_name(block.start)
+                )?;
+            }
+        }
+        if unresolved {
+            writeln!(writer, "    unresolved [label=\"?\", shape=circle];")?;
+        }
+        let not_code: BTreeSet<usize> = self
+            .blocks
+            .values()
+            .flat_map(|block| &block.successors)
+            .map(|edge| edge.target)
+            .filter(|target| !self.blocks.contains_key(target))
+            .collect();
+        for target in not_code {
+            writeln!(
+                writer,
+                "    {} [label=\"{}:\\lnot code\\l\", style=dashed];",
+                label_name(target),
+                label_name(target)
+            )?;
+        }
+        writeln!(writer, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble::assemble;
+
+    fn edge(target: usize, kind: EdgeKind) -> Edge {
+        Edge { target, kind }
+    }
+
+    fn starts(graph: &ControlFlowGraph) -> Vec<usize> {
+        graph.blocks.keys().copied().collect()
+    }
+
+    #[test]
+    fn splits_blocks_at_jump_targets() {
+        let program = assemble(
+            "        IN -> [x]
+             loop:   ADD [x], #-1 -> [x]
+                     JNZ [x], #loop
+                     HLT
+             x:      DATA 0",
+        )
+        .unwrap();
+        let graph = control_flow_graph(&program);
+        assert_eq!(starts(&graph), vec![0, 2, 9]);
+        assert_eq!(
+            graph.blocks[&0].successors,
+            vec![edge(2, EdgeKind::Fallthrough)]
+        );
+        assert_eq!(
+            graph.blocks[&2].successors,
+            vec![edge(2, EdgeKind::Jump), edge(9, EdgeKind::Fallthrough)]
+        );
+        assert_eq!(graph.blocks[&2].instructions.len(), 2);
+        assert_eq!(graph.blocks[&2].end(), 9);
+        assert!(graph.blocks[&9].successors.is_empty());
+        assert_eq!(graph.predecessors(2).collect::<Vec<_>>(), vec![0, 2]);
+        assert!(graph.blocks.values().all(|block| !block.unresolved));
+    }
+
+    #[test]
+    fn marks_indirect_jumps_unresolved() {
+        let program = assemble(
+            "        ADD #back, #0 -> [r]
+                     JNZ #1, #function
+             back:   OUT #1
+                     HLT
+             function:
+                     JNZ #1, [r]",
+        )
+        .unwrap();
+        let graph = control_flow_graph(&program);
+        assert_eq!(starts(&graph), vec![0, 7, 10]);
+        assert_eq!(
+            graph.blocks[&0].successors,
+            vec![edge(10, EdgeKind::Jump), edge(7, EdgeKind::Return)]
+        );
+        assert!(graph.blocks[&10].unresolved);
+        assert!(graph.blocks[&10].successors.is_empty());
+        assert!(!graph.blocks[&0].unresolved);
+
+        let mut dot = Vec::new();
+        graph.write_dot(&mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert!(dot.contains("    L0000 -> L0007 [style=dotted, label=\"return\"];\n"));
+        assert!(dot.contains("    L0010 -> unresolved [style=dashed];\n"));
+        assert!(dot.contains("    unresolved [label=\"?\", shape=circle];\n"));
+    }
+
+    #[test]
+    fn gives_targets_outside_the_code_their_own_node() {
+        // Jumps onto a word that isn't an instruction, and into the middle of an instruction.
+        let graph = control_flow_graph(&[1006, 9, 6, 1105, 1, 4, 42, 0, 0, 0]);
+        assert_eq!(starts(&graph), vec![0, 3]);
+        assert_eq!(
+            graph.blocks[&0].successors,
+            vec![edge(6, EdgeKind::Jump), edge(3, EdgeKind::Fallthrough)]
+        );
+        assert_eq!(graph.blocks[&3].successors, vec![edge(4, EdgeKind::Jump)]);
+
+        let mut dot = Vec::new();
+        graph.write_dot(&mut dot).unwrap();
+        assert_eq!(
+            String::from_utf8(dot).unwrap(),
+            r#"digraph cfg {
+    node [shape=box, fontname="monospace"];
+    L0000 [label="L0000:\l    0  JZ [9], #L0006\l"];
+    L0003 [label="L0003:\l    3  JNZ #1, #L0004\l"];
+    L0000 -> L0006 [label="jump"];
+    L0000 -> L0003 [color=gray40];
+    L0003 -> L0004 [label="jump"];
+    L0004 [label="L0004:\lnot code\l", style=dashed];
+    L0006 [label="L0006:\lnot code\l", style=dashed];
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Argument handling shared by the command line tools in `src/bin`, so they all report usage
+//! errors and unreadable files the same way and exit with the same status.
+
+use crate::computer::{parse_program, Computer, Program};
+use std::{env, fmt::Display, fs, fs::File, io, process};
+
+/// Exit status of a tool that was used wrongly or couldn't do its job.
+pub const FAILURE: i32 = 1;
+
+/// Prints `message` to stderr and exits with `FAILURE`.
+pub fn fail(message: impl Display) -> ! {
+    eprintln!("{}", message);
+    process::exit(FAILURE)
+}
+
+/// Prints `Usage: ` followed by `usage` and exits with `FAILURE`.
+pub fn usage(usage: &str) -> ! {
+    fail(format_args!("Usage: {}", usage))
+}
+
+/// The command line arguments, without the tool's name.
+pub fn args() -> Vec<String> {
+    env::args().skip(1).collect()
+}
+
+/// Removes a leading `option <value>` pair from `args` and returns the value.
+pub fn take_option(args: &mut Vec<String>, option: &str) -> Option<String> {
+    if args.len() >= 2 && args[0] == option {
+        args.remove(0);
+        Some(args.remove(0))
+    } else {
+        None
+    }
+}
+
+/// The only argument of a tool that works on one file, exiting with `usage` if it's missing.
+pub fn path_arg(usage: &str) -> String {
+    match env::args().nth(1) {
+        Some(path) => path,
+        None => self::usage(usage),
+    }
+}
+
+/// Reads the program named by the only argument, exiting with `usage` if it's missing.
+pub fn read_program_arg(usage: &str) -> Program {
+    read_program(&path_arg(usage))
+}
+
+/// Reads a program from `path`, exiting if the file can't be read.
+pub fn read_program(path: &str) -> Program {
+    parse_program(&read_file(path))
+}
+
+/// Reads the whole file at `path`, exiting if it can't be read.
+pub fn read_file(path: &str) -> String {
+    fs::read_to_string(path).unwrap_or_else(|err| unreadable(path, err))
+}
+
+/// Opens the file at `path`, exiting if it can't be read.
+pub fn open_file(path: &str) -> File {
+    File::open(path).unwrap_or_else(|err| unreadable(path, err))
+}
+
+/// Queues the input values given on the command line, exiting if one isn't a number.
+pub fn push_inputs(computer: &mut Computer, values: &[String]) {
+    for value in values {
+        match value.parse() {
+            Ok(value) => computer.push_input(value),
+            Err(_) => fail(format_args!("Invalid input value `{}`", value)),
+        }
+    }
+}
+
+fn unreadable(path: &str, err: io::Error) -> ! {
+    fail(format_args!("Could not read {}: {}", path, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn takes_a_leading_option_with_its_value() {
+        let mut args = strings(&["--lcov", "out.info", "day09.txt", "1"]);
+        assert_eq!(
+            take_option(&mut args, "--lcov"),
+            Some("out.info".to_owned())
+        );
+        assert_eq!(args, strings(&["day09.txt", "1"]));
+
+        let mut args = strings(&["day09.txt", "--lcov", "out.info"]);
+        assert_eq!(take_option(&mut args, "--lcov"), None);
+        assert_eq!(args.len(), 3);
+
+        let mut args = strings(&["--lcov"]);
+        assert_eq!(take_option(&mut args, "--lcov"), None);
+        assert_eq!(args, strings(&["--lcov"]));
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{
+    disassemble::label_name,
+    observer::{StepEvent, StepObserver},
+    word::Word,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+// How an address has been executed so far, as bit flags.
+const AS_OPCODE: u8 = 1;
+const AS_PARAMETER: u8 = 2;
+
+/// A program writing to an address that is also executed as code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CodeWrite {
+    /// Address of the instruction that wrote.
+    pub writer: usize,
+    pub target: usize,
+    /// Whether `target` was executed as the opcode of an instruction, rather than only as one of
+    /// its parameters.
+    pub opcode: bool,
+    /// Whether `target` had already been executed when it was written. Otherwise the write
+    /// patched code that only ran later.
+    pub after_execution: bool,
+    /// Step number of the first such write, counting from zero.
+    pub first_step: u64,
+    pub count: u64,
+}
+
+/// Reports writes into the program's own instruction stream.
+///
+/// A write is reported when its target has already been executed, or as soon as the target is
+/// executed after the write. Only writes made by the program itself are seen, not patches made
+/// to the memory before it ran.
+#[derive(Debug, Clone, Default)]
+pub struct CodeWriteDetector {
+    steps: u64,
+    executed: HashMap<usize, u8>,
+    // The last write to each address that hasn't been executed yet, with its writer and step.
+    pending: HashMap<usize, (usize, u64)>,
+    writes: BTreeMap<(usize, usize, bool), CodeWrite>,
+}
+
+impl CodeWriteDetector {
+    pub fn new() -> CodeWriteDetector {
+        CodeWriteDetector::default()
+    }
+
+    /// Every distinct combination of writer, target and order found so far, by writer address.
+    pub fn writes(&self) -> impl Iterator<Item = &CodeWrite> + '_ {
+        self.writes.values()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Writes one line per reported write, in the order of `writes`.
+    pub fn write_report<O: Write>(&self, mut writer: O) -> io::Result<()> {
+        for write in self.writes() {
+            writeln!(
+                writer,
+                "{} wrote {} {} {}, {} time{} starting at step {}",
+                label_name(write.writer),
+                if write.opcode { "opcode" } else { "parameter" },
+                label_name(write.target),
+                if write.after_execution {
+                    "after it ran"
+                } else {
+                    "before it ran"
+                },
+                write.count,
+                if write.count == 1 { "" } else { "s" },
+                write.first_step
+            )?;
+        }
+        Ok(())
+    }
+
+    fn executed(&self, address: usize) -> u8 {
+        self.executed.get(&address).copied().unwrap_or(0)
+    }
+
+    fn record(&mut self, writer: usize, target: usize, step: u64, after_execution: bool) {
+        let opcode = self.executed(target) & AS_OPCODE != 0;
+        self.writes
+            .entry((writer, target, after_execution))
+            .or_insert(CodeWrite {
+                writer,
+                target,
+                opcode,
+                after_execution,
+                first_step: step,
+                count: 0,
+            })
+            .count += 1;
+    }
+
+    fn execute(&mut self, address: usize, kind: u8) {
+        let executed = self.executed.entry(address).or_insert(0);
+        let first = *executed == 0;
+        *executed |= kind;
+        if first {
+            if let Some((writer, step)) = self.pending.remove(&address) {
+                self.record(writer, address, step, false);
+            }
+        }
+    }
+}
+
+impl<W: Word> StepObserver<W> for CodeWriteDetector {
+    fn on_step(&mut self, event: &StepEvent<W>) {
+        self.execute(event.ip, AS_OPCODE);
+        for address in event.ip + 1..event.ip + event.instruction.size() {
+            self.execute(address, AS_PARAMETER);
+        }
+        if let Some(write) = &event.write {
+            if self.executed(write.address) != 0 {
+                self.record(event.ip, write.address, self.steps, true);
+            } else {
+                self.pending.insert(write.address, (event.ip, self.steps));
+            }
+        }
+        self.steps += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assemble::assemble,
+        computer::{Computer, ComputerState},
+    };
+
+    fn detect(source: &str) -> (CodeWriteDetector, Vec<i64>) {
+        let mut computer = Computer::new(assemble(source).unwrap());
+        let mut detector = CodeWriteDetector::new();
+        let mut outputs = Vec::new();
+        while let ComputerState::Output(output) = computer.execute_with(&mut detector).unwrap() {
+            outputs.push(output);
+        }
+        (detector, outputs)
+    }
+
+    #[test]
+    fn finds_code_written_before_it_runs() {
+        let (detector, outputs) = detect(
+            "        ADD #104, #0 -> [patch]
+                     ADD #5, #0 -> [patch+1]
+             patch:  DATA 0, 0
+                     HLT",
+        );
+        assert_eq!(outputs, vec![5]);
+        assert_eq!(
+            detector.writes().copied().collect::<Vec<_>>(),
+            vec![
+                CodeWrite {
+                    writer: 0,
+                    target: 8,
+                    opcode: true,
+                    after_execution: false,
+                    first_step: 0,
+                    count: 1,
+                },
+                CodeWrite {
+                    writer: 4,
+                    target: 9,
+                    opcode: false,
+                    after_execution: false,
+                    first_step: 1,
+                    count: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_code_written_after_it_ran() {
+        let (detector, outputs) = detect(
+            "loop:   OUT #1
+                     ADD [loop+1], #1 -> [loop+1]
+                     LT [loop+1], #3 -> [more]
+                     JNZ [more], #loop
+                     ADD #99, #0 -> [loop]
+                     HLT
+             more:   DATA 0",
+        );
+        assert_eq!(outputs, vec![1, 2]);
+        let writes: Vec<CodeWrite> = detector.writes().copied().collect();
+        assert_eq!(
+            writes,
+            vec![
+                CodeWrite {
+                    writer: 2,
+                    target: 1,
+                    opcode: false,
+                    after_execution: true,
+                    first_step: 1,
+                    count: 2,
+                },
+                CodeWrite {
+                    writer: 13,
+                    target: 0,
+                    opcode: true,
+                    after_execution: true,
+                    first_step: 8,
+                    count: 1,
+                },
+            ]
+        );
+
+        let mut report = Vec::new();
+        detector.write_report(&mut report).unwrap();
+        assert_eq!(
+            String::from_utf8(report).unwrap(),
+            "L0002 wrote parameter L0001 after it ran, 2 times starting at step 1
+L0013 wrote opcode L0000 after it ran, 1 time starting at step 8
+"
+        );
+    }
+
+    #[test]
+    fn ignores_writes_to_data() {
+        let (detector, _) = detect(
+            "        ADD #1, #2 -> [x]
+                     ADD [x], #1 -> [x]
+                     HLT
+             x:      DATA 0",
+        );
+        assert!(detector.is_empty());
+    }
+
+    #[test]
+    fn finds_code_written_at_high_addresses() {
+        // Writes a `HLT` at 2^40 and jumps to it.
+        let high: i64 = 1 << 40;
+        let mut computer = Computer::new(vec![1101, 99, 0, high, 1105, 1, high]);
+        let mut detector = CodeWriteDetector::new();
+        assert_eq!(
+            computer.execute_with(&mut detector),
+            Ok(ComputerState::Halt)
+        );
+        let mut report = Vec::new();
+        detector.write_report(&mut report).unwrap();
+        assert_eq!(
+            String::from_utf8(report).unwrap(),
+            "L0000 wrote opcode L1099511627776 before it ran, 1 time starting at step 0\n"
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use crate::{
+    decode_cache::DecodeCache,
+    disassemble::find_code,
+    error::IntcodeError,
+    instruction::{Header, Instruction, Opcode, Parameter, ParameterMode},
+    memory::Memory,
+    observer::{MemoryWrite, StepEvent, StepObserver},
+    record::{self, IoEvent, Recorder, Recording},
+    word::Word,
+};
+use std::{collections::VecDeque, convert::TryFrom, io};
+
+pub type Program<W = i64> = Vec<W>;
+
+pub fn parse_program(input: &str) -> Program {
+    parse_words(input)
+}
+
+/// Like `parse_program`, for computers with a word type other than `i64`.
+pub fn parse_words<W: Word>(input: &str) -> Program<W> {
+    input
+        .trim()
+        .split(',')
+        .map(|num| {
+            num.parse::<W>()
+                .unwrap_or_else(|_| panic!("Invalid program word `{}`", num))
+        })
+        .collect()
+}
+
+/// An Intcode computer whose memory holds words of type `W`, see `Word`.
+#[derive(Debug, Clone)]
+pub struct Computer<W = i64> {
+    memory: Memory<W>,
+    ip: usize,
+    relative_base_offset: i64,
+    pub inputs: VecDeque<W>,
+    decoded: DecodeCache,
+    recording: Option<Recorder<W>>,
+}
+
+/// Outcome of executing a single instruction with `Computer::step`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StepResult<W = i64> {
+    Continue,
+    Stop,
+    Output(W),
+    InputRequired,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ComputerState<W = i64> {
+    Halt,
+    Output(W),
+    InputRequired,
+    /// `execute_with_budget` ran out of steps before anything else happened.
+    BudgetExhausted,
+    /// `execute_detecting_loops` found the computer back in a state it was in `period` steps
+    /// earlier, without any output or input in between, so it would run forever.
+    LoopDetected {
+        period: u64,
+    },
+}
+
+impl<W: Word> Computer<W> {
+    pub fn new(program: Program<W>) -> Computer<W> {
+        Computer {
+            memory: Memory::from(program),
+            ip: 0,
+            relative_base_offset: 0,
+            inputs: VecDeque::new(),
+            decoded: DecodeCache::new(),
+            recording: None,
+        }
+    }
+
+    pub(crate) fn from_parts(
+        memory: Memory<W>,
+        ip: usize,
+        relative_base_offset: i64,
+        inputs: VecDeque<W>,
+    ) -> Computer<W> {
+        Computer {
+            memory,
+            ip,
+            relative_base_offset,
+            inputs,
+            decoded: DecodeCache::new(),
+            recording: None,
+        }
+    }
+
+    pub fn memory(&self) -> &Memory<W> {
+        &self.memory
+    }
+
+    pub fn set_memory(&mut self, address: usize, value: W) {
+        self.memory.set(address, value);
+        self.decoded.invalidate(address);
+    }
+
+    /// Turns the cache of decoded instructions on or off. It is on by default; turning it off
+    /// makes every step decode its instruction from memory again.
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.decoded.set_enabled(enabled);
+    }
+
+    pub fn decode_cache_enabled(&self) -> bool {
+        self.decoded.is_enabled()
+    }
+
+    /// Decodes every instruction reachable from the current memory up front. Clones of a warmed
+    /// up computer share its decoded instructions, which is what makes the cache pay off for
+    /// short-lived computers.
+    pub fn warm_decode_cache(&mut self) {
+        // Words too large for the analysis can't be opcodes, and as parameters at worst make it
+        // decode more than is reachable.
+        let words: Vec<i64> = self
+            .memory
+            .dense()
+            .map(|word| word.to_i64().unwrap_or(0))
+            .collect();
+        for (address, instruction) in find_code(&words).0 {
+            if let Ok(header) = Header::decode(address, &instruction.word) {
+                self.decoded.insert_shared(address, header);
+            }
+        }
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base_offset
+    }
+
+    pub(crate) fn set_position(&mut self, ip: usize, relative_base_offset: i64) {
+        self.ip = ip;
+        self.relative_base_offset = relative_base_offset;
+    }
+
+    /// Decodes the instruction the computer is about to execute.
+    #[inline]
+    pub fn current_instruction(&self) -> Result<Instruction<W>, IntcodeError<W>> {
+        Instruction::decode(self.ip, |address| self.memory.get(address))
+    }
+
+    pub fn push_input(&mut self, value: W) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(IoEvent::Input(value.clone()));
+        }
+        self.inputs.push_front(value);
+    }
+
+    /// Starts recording every value passed to `push_input`, every output and halting, in the
+    /// order they happen, discarding any earlier recording. Clones carry on recording on their
+    /// own, sharing what was recorded before they were made.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recorder::new());
+    }
+
+    /// Starts recording if the `INTCODE_RECORD` environment variable is set, for saving with
+    /// `save_requested_recording`.
+    pub fn record_if_requested(&mut self) {
+        if record::requested() {
+            self.start_recording();
+        }
+    }
+
+    /// What was recorded so far, if the computer is recording.
+    pub fn recording(&self) -> Option<Recording<W>> {
+        self.recording.as_ref().map(Recorder::recording)
+    }
+
+    /// Stops recording and returns what was recorded, if the computer was recording.
+    pub fn take_recording(&mut self) -> Option<Recording<W>> {
+        self.recording.take().as_ref().map(Recorder::recording)
+    }
+
+    /// Saves what was recorded so far to the file named by the `INTCODE_RECORD` environment
+    /// variable, if it is set and the computer is recording.
+    pub fn save_requested_recording(&self) -> io::Result<()> {
+        match self.recording() {
+            Some(recording) => record::save_requested(&recording),
+            None => Ok(()),
+        }
+    }
+
+    pub fn execute(&mut self) -> Result<ComputerState<W>, IntcodeError<W>> {
+        self.execute_with(&mut ())
+    }
+
+    /// Like `execute`, but reports every executed instruction to `observer`.
+    pub fn execute_with<O: StepObserver<W>>(
+        &mut self,
+        observer: &mut O,
+    ) -> Result<ComputerState<W>, IntcodeError<W>> {
+        loop {
+            match self.step_with(observer)? {
+                StepResult::Stop => break,
+                StepResult::Output(output) => return Ok(ComputerState::Output(output)),
+                StepResult::InputRequired => return Ok(ComputerState::InputRequired),
+                StepResult::Continue => {}
+            }
+        }
+        Ok(ComputerState::Halt)
+    }
+
+    #[inline]
+    fn check_address(&self, address: &W) -> Result<usize, IntcodeError<W>> {
+        match address
+            .to_i64()
+            .and_then(|address| usize::try_from(address).ok())
+        {
+            Some(address) => Ok(address),
+            None => Err(self.address_error(address)),
+        }
+    }
+
+    #[cold]
+    fn address_error(&self, address: &W) -> IntcodeError<W> {
+        let (ip, opcode, address) = (self.ip, self.memory.get(self.ip), address.clone());
+        if address < W::zero() {
+            IntcodeError::NegativeAddress {
+                ip,
+                opcode,
+                address,
+            }
+        } else {
+            IntcodeError::AddressTooLarge {
+                ip,
+                opcode,
+                address,
+            }
+        }
+    }
+
+    #[cold]
+    fn overflow(&self) -> IntcodeError<W> {
+        IntcodeError::Overflow {
+            ip: self.ip,
+            opcode: self.memory.get(self.ip),
+        }
+    }
+
+    #[inline]
+    fn relative_address(&self, offset: &W) -> Result<usize, IntcodeError<W>> {
+        let address = W::from_i64(self.relative_base_offset)
+            .checked_add(offset)
+            .ok_or_else(|| self.overflow())?;
+        self.check_address(&address)
+    }
+
+    #[inline]
+    fn read_parameter(&self, parameter: &Parameter<W>) -> Result<W, IntcodeError<W>> {
+        match parameter.mode {
+            ParameterMode::Immediate => Ok(parameter.value.clone()),
+            ParameterMode::Position => Ok(self.memory.get(self.check_address(&parameter.value)?)),
+            ParameterMode::Relative => {
+                Ok(self.memory.get(self.relative_address(&parameter.value)?))
+            }
+        }
+    }
+
+    #[inline]
+    fn write_address(&self, parameter: &Parameter<W>) -> Result<usize, IntcodeError<W>> {
+        match parameter.mode {
+            ParameterMode::Relative => self.relative_address(&parameter.value),
+            _ => self.check_address(&parameter.value),
+        }
+    }
+
+    /// Executes a single instruction. An instruction that needs input which isn't available yet
+    /// leaves the computer untouched and reports `StepResult::InputRequired`.
+    pub fn step(&mut self) -> Result<StepResult<W>, IntcodeError<W>> {
+        self.step_with(&mut ())
+    }
+
+    /// Like `step`, but reports the executed instruction to `observer`.
+    #[inline(always)]
+    pub fn step_with<O: StepObserver<W>>(
+        &mut self,
+        observer: &mut O,
+    ) -> Result<StepResult<W>, IntcodeError<W>> {
+        let word = self.memory.get(self.ip);
+        let header = match self.decoded.get(self.ip) {
+            Some(header) => header,
+            None => {
+                let header = Header::decode(self.ip, &word)?;
+                self.decoded.insert(self.ip, header);
+                header
+            }
+        };
+        let instruction =
+            Instruction::from_header(self.ip, word, header, |address| self.memory.get(address));
+        let opcode = instruction.opcode;
+        let next_ip = self.ip + instruction.size();
+        let params = instruction.all_parameters().clone();
+        let mut event = StepEvent::new(self.ip, instruction, self.relative_base_offset);
+        let mut result = StepResult::Continue;
+        let mut write = None;
+        event.next_ip = next_ip;
+
+        match opcode {
+            Opcode::Halt => {
+                event.next_ip = self.ip;
+                result = StepResult::Stop;
+                if let Some(recording) = &mut self.recording {
+                    recording.push(IoEvent::Halt);
+                }
+            }
+            Opcode::Add => {
+                let a = self.read_operand(&mut event, &params[0])?;
+                let b = self.read_operand(&mut event, &params[1])?;
+                let sum = a.checked_add(&b).ok_or_else(|| self.overflow())?;
+                write = Some((self.write_address(&params[2])?, sum));
+            }
+            Opcode::Mul => {
+                let a = self.read_operand(&mut event, &params[0])?;
+                let b = self.read_operand(&mut event, &params[1])?;
+                let product = a.checked_mul(&b).ok_or_else(|| self.overflow())?;
+                write = Some((self.write_address(&params[2])?, product));
+            }
+            Opcode::Input => {
+                let addr = self.write_address(&params[0])?;
+                match self.inputs.pop_back() {
+                    Some(input) => {
+                        event.input = Some(input.clone());
+                        write = Some((addr, input));
+                    }
+                    None => return Ok(StepResult::InputRequired),
+                }
+            }
+            Opcode::Output => {
+                let output = self.read_operand(&mut event, &params[0])?;
+                event.output = Some(output.clone());
+                if let Some(recording) = &mut self.recording {
+                    recording.push(IoEvent::Output(output.clone()));
+                }
+                // Return execution back to operator
+                result = StepResult::Output(output);
+            }
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                let condition = self.read_operand(&mut event, &params[0])?;
+                if condition.is_zero() != (opcode == Opcode::JumpIfTrue) {
+                    let target = self.read_operand(&mut event, &params[1])?;
+                    event.next_ip = self.check_address(&target)?;
+                }
+            }
+            Opcode::LessThan => {
+                let a = self.read_operand(&mut event, &params[0])?;
+                let b = self.read_operand(&mut event, &params[1])?;
+                write = Some((self.write_address(&params[2])?, W::from_i64((a < b) as i64)));
+            }
+            Opcode::Equals => {
+                let a = self.read_operand(&mut event, &params[0])?;
+                let b = self.read_operand(&mut event, &params[1])?;
+                write = Some((
+                    self.write_address(&params[2])?,
+                    W::from_i64((a == b) as i64),
+                ));
+            }
+            Opcode::AdjustRelativeBase => {
+                let offset = self.read_operand(&mut event, &params[0])?;
+                self.relative_base_offset = offset
+                    .to_i64()
+                    .and_then(|offset| self.relative_base_offset.checked_add(offset))
+                    .ok_or_else(|| self.overflow())?;
+                event.relative_base = self.relative_base_offset;
+            }
+        }
+
+        if let Some((address, value)) = write {
+            event.write = Some(MemoryWrite {
+                address,
+                old: self.memory.get(address),
+                new: value.clone(),
+            });
+            self.memory.set(address, value);
+            self.decoded.invalidate(address);
+        }
+        self.ip = event.next_ip;
+        observer.on_step(&event);
+        Ok(result)
+    }
+
+    #[inline]
+    fn read_operand(
+        &self,
+        event: &mut StepEvent<W>,
+        parameter: &Parameter<W>,
+    ) -> Result<W, IntcodeError<W>> {
+        let value = self.read_parameter(parameter)?;
+        event.push_operand(value.clone());
+        Ok(value)
+    }
+}
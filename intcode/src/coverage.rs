@@ -0,0 +1,252 @@
+use crate::{
+    disassemble::{disassemble, Disassembly, Entry},
+    instruction::Opcode,
+    observer::{StepEvent, StepObserver},
+    word::Word,
+};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    ops::Range,
+};
+
+/// How often a conditional instruction went each way.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct BranchCount {
+    /// Jumps taken, or comparisons that stored 1.
+    pub taken: u64,
+    /// Jumps that fell through, or comparisons that stored 0.
+    pub not_taken: u64,
+}
+
+/// Records which addresses were executed and which way every `JNZ`, `JZ`, `LT` and `EQ` went, and
+/// reports it over the disassembled program.
+///
+/// The report lists each line of the `disassemble` listing with its execution count, `#####` for
+/// instructions that never ran, and branch outcomes after conditional instructions. Code that
+/// ran but isn't in the listing, because it is only reached through computed jumps or was written
+/// at runtime, shows up as executed data. Observing several runs with the same `Coverage`, or
+/// merging them, adds their counts up.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    hits: BTreeMap<usize, u64>,
+    branches: BTreeMap<usize, BranchCount>,
+}
+
+impl Coverage {
+    pub fn new() -> Coverage {
+        Coverage::default()
+    }
+
+    /// Number of times the instruction at `address` was executed.
+    pub fn hits(&self, address: usize) -> u64 {
+        self.hits.get(&address).copied().unwrap_or(0)
+    }
+
+    /// Outcomes of the conditional instruction at `address`, if one was executed there.
+    pub fn branch(&self, address: usize) -> Option<BranchCount> {
+        self.branches.get(&address).copied()
+    }
+
+    /// Adds the counts of `other`, for combining separate runs of the same program.
+    pub fn merge(&mut self, other: &Coverage) {
+        for (address, other) in &other.hits {
+            *self.hits.entry(*address).or_default() += other;
+        }
+        for (address, other) in &other.branches {
+            let branch = self.branches.entry(*address).or_default();
+            branch.taken += other.taken;
+            branch.not_taken += other.not_taken;
+        }
+    }
+
+    /// Writes the listing of `program` annotated with execution counts, after a summary of how
+    /// many instructions and branch outcomes were covered.
+    pub fn write_report<O: Write>(&self, program: &[i64], mut writer: O) -> io::Result<()> {
+        let disassembly = disassemble(program);
+        let listing = disassembly.to_string();
+        let lines = lines(&listing, &disassembly);
+
+        let (mut instructions, mut executed, mut outcomes, mut covered) = (0, 0, 0, 0);
+        for line in &lines {
+            if let Some(Entry::Instruction {
+                address,
+                instruction,
+            }) = line.entry
+            {
+                instructions += 1;
+                executed += (self.hits(*address) > 0) as usize;
+                if is_branch(instruction.opcode) {
+                    let branch = self.branch(*address).unwrap_or_default();
+                    outcomes += 2;
+                    covered += (branch.taken > 0) as usize + (branch.not_taken > 0) as usize;
+                }
+            }
+        }
+        let percent = |part: usize, total: usize| 100.0 * part as f64 / total.max(1) as f64;
+        writeln!(
+            writer,
+            "Instructions: {} of {} executed ({:.2}%)",
+            executed,
+            instructions,
+            percent(executed, instructions)
+        )?;
+        writeln!(
+            writer,
+            "Branches: {} of {} outcomes covered ({:.2}%)",
+            covered,
+            outcomes,
+            percent(covered, outcomes)
+        )?;
+        writeln!(writer)?;
+
+        for line in &lines {
+            let (count, branch) = match line.entry {
+                Some(Entry::Instruction {
+                    address,
+                    instruction,
+                }) => {
+                    let count = match self.hits(*address) {
+                        0 => "#####".to_owned(),
+                        hits => hits.to_string(),
+                    };
+                    let branch = self.branch(*address).unwrap_or_default();
+                    let branch = match instruction.opcode {
+                        Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                            format!("  [taken {}, not taken {}]", branch.taken, branch.not_taken)
+                        }
+                        Opcode::LessThan | Opcode::Equals => {
+                            format!("  [true {}, false {}]", branch.taken, branch.not_taken)
+                        }
+                        _ => String::new(),
+                    };
+                    (count, branch)
+                }
+                Some(Entry::Data { address, values }) => {
+                    match self.range_hits(*address..address + values.len()) {
+                        0 => (String::new(), String::new()),
+                        hits => (hits.to_string(), "  [executed]".to_owned()),
+                    }
+                }
+                None => (String::new(), String::new()),
+            };
+            writeln!(writer, "{:>9}  {}{}", count, line.text, branch)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the coverage as an lcov tracefile for `source`, a file holding the `disassemble`
+    /// listing of `program`. Lines are lines of that listing, and every conditional instruction
+    /// is a branch with two outcomes, taken or true first.
+    pub fn write_lcov<O: Write>(
+        &self,
+        program: &[i64],
+        source: &str,
+        mut writer: O,
+    ) -> io::Result<()> {
+        let disassembly = disassemble(program);
+        let listing = disassembly.to_string();
+
+        writeln!(writer, "TN:")?;
+        writeln!(writer, "SF:{}", source)?;
+        let (mut found, mut hit, mut branches_found, mut branches_hit) = (0, 0, 0, 0);
+        for (number, line) in lines(&listing, &disassembly).iter().enumerate() {
+            let number = number + 1;
+            match line.entry {
+                Some(Entry::Instruction {
+                    address,
+                    instruction,
+                }) => {
+                    let hits = self.hits(*address);
+                    writeln!(writer, "DA:{},{}", number, hits)?;
+                    found += 1;
+                    hit += (hits > 0) as usize;
+                    if !is_branch(instruction.opcode) {
+                        continue;
+                    }
+                    let branch = self.branch(*address).unwrap_or_default();
+                    for (index, &count) in [branch.taken, branch.not_taken].iter().enumerate() {
+                        if hits == 0 {
+                            writeln!(writer, "BRDA:{},0,{},-", number, index)?;
+                        } else {
+                            writeln!(writer, "BRDA:{},0,{},{}", number, index, count)?;
+                        }
+                        branches_found += 1;
+                        branches_hit += (count > 0) as usize;
+                    }
+                }
+                Some(Entry::Data { address, values }) => {
+                    let hits = self.range_hits(*address..address + values.len());
+                    if hits > 0 {
+                        writeln!(writer, "DA:{},{}", number, hits)?;
+                        found += 1;
+                        hit += 1;
+                    }
+                }
+                None => {}
+            }
+        }
+        writeln!(writer, "BRF:{}", branches_found)?;
+        writeln!(writer, "BRH:{}", branches_hit)?;
+        writeln!(writer, "LF:{}", found)?;
+        writeln!(writer, "LH:{}", hit)?;
+        writeln!(writer, "end_of_record")
+    }
+
+    fn range_hits(&self, addresses: Range<usize>) -> u64 {
+        self.hits.range(addresses).map(|(_, hits)| hits).sum()
+    }
+}
+
+impl<W: Word> StepObserver<W> for Coverage {
+    fn on_step(&mut self, event: &StepEvent<W>) {
+        *self.hits.entry(event.ip).or_default() += 1;
+
+        let taken = match event.instruction.opcode {
+            // The target is only read when the jump is taken.
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => event.operands().len() == 2,
+            Opcode::LessThan | Opcode::Equals => event
+                .write
+                .as_ref()
+                .is_some_and(|write| !write.new.is_zero()),
+            _ => return,
+        };
+        let branch = self.branches.entry(event.ip).or_default();
+        if taken {
+            branch.taken += 1;
+        } else {
+            branch.not_taken += 1;
+        }
+    }
+}
+
+fn is_branch(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::JumpIfTrue | Opcode::JumpIfFalse | Opcode::LessThan | Opcode::Equals
+    )
+}
+
+// A line of a disassembly listing, with the entry it shows unless it is a label.
+struct Line<'a> {
+    text: &'a str,
+    entry: Option<&'a Entry>,
+}
+
+fn lines<'a>(listing: &'a str, disassembly: &'a Disassembly) -> Vec<Line<'a>> {
+    let mut text = listing.lines();
+    let mut lines = Vec::new();
+    for entry in &disassembly.entries {
+        let address = match entry {
+            Entry::Instruction { address, .. } | Entry::Data { address, .. } => address,
+        };
+        if disassembly.labels.contains(address) {
+            lines.extend(text.next().map(|text| Line { text, entry: None }));
+        }
+        lines.extend(text.next().map(|text| Line {
+            text,
+            entry: Some(entry),
+        }));
+    }
+    lines
+}
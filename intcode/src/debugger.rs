@@ -0,0 +1,250 @@
+use crate::{
+    computer::{Computer, StepResult},
+    error::IntcodeError,
+    undo::UndoLog,
+    word::Word,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Why the debugger handed control back to the user.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StopReason<W = i64> {
+    /// The requested number of instructions was executed.
+    Stepped,
+    Breakpoint(usize),
+    Watchpoint {
+        address: usize,
+        old: W,
+        new: W,
+    },
+    RelativeBaseChanged {
+        old: i64,
+        new: i64,
+    },
+    Output(W),
+    InputRequired,
+    Halt,
+    Error(IntcodeError<W>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Until {
+    Steps(usize),
+    Break,
+    Output,
+    Input,
+}
+
+/// Wraps a `Computer` with breakpoints on `ip` and watchpoints on memory or the relative base.
+#[derive(Debug, Clone)]
+pub struct Debugger<W = i64> {
+    pub computer: Computer<W>,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeMap<usize, W>,
+    watch_relative_base: bool,
+    outputs: Vec<W>,
+    steps: usize,
+    history: UndoLog<W>,
+}
+
+impl<W: Word> Debugger<W> {
+    pub fn new(computer: Computer<W>) -> Debugger<W> {
+        Debugger {
+            computer,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeMap::new(),
+            watch_relative_base: false,
+            outputs: Vec::new(),
+            steps: 0,
+            history: UndoLog::new(0),
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, ip: usize) {
+        self.breakpoints.insert(ip);
+    }
+
+    pub fn remove_breakpoint(&mut self, ip: usize) -> bool {
+        self.breakpoints.remove(&ip)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    pub fn add_watchpoint(&mut self, address: usize) {
+        let value = self.computer.memory().get(address);
+        self.watchpoints.insert(address, value);
+    }
+
+    pub fn remove_watchpoint(&mut self, address: usize) -> bool {
+        self.watchpoints.remove(&address).is_some()
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.watchpoints.keys().copied()
+    }
+
+    pub fn set_watch_relative_base(&mut self, enabled: bool) {
+        self.watch_relative_base = enabled;
+    }
+
+    pub fn watches_relative_base(&self) -> bool {
+        self.watch_relative_base
+    }
+
+    /// Writes to memory on behalf of the user, without triggering the watchpoint on that address.
+    pub fn set_memory(&mut self, address: usize, value: W) {
+        self.computer.set_memory(address, value.clone());
+        if let Some(watched) = self.watchpoints.get_mut(&address) {
+            *watched = value;
+        }
+    }
+
+    /// Swaps in another computer, e.g. one restored from a snapshot, keeping breakpoints and
+    /// watchpoints.
+    pub fn replace_computer(&mut self, computer: Computer<W>) {
+        self.computer = computer;
+        self.history.clear();
+        self.refresh_watchpoints();
+    }
+
+    /// Keeps the last `limit` executed instructions so they can be undone with `step_back`.
+    /// Zero, the default, turns reverse stepping off.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history.set_limit(limit);
+    }
+
+    /// Number of instructions `step_back` can currently undo.
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Undoes up to `count` instructions and returns how many were undone. Watchpoints don't
+    /// trigger while stepping back.
+    pub fn step_back(&mut self, count: usize) -> usize {
+        let mut undone = 0;
+        while undone < count && self.computer.step_back(&mut self.history) {
+            undone += 1;
+        }
+        self.steps -= undone;
+        self.refresh_watchpoints();
+        undone
+    }
+
+    /// Steps back to just before the most recent remembered write to `address`, returning the
+    /// number of instructions undone. Nothing changes if no such write is remembered.
+    pub fn run_back_to_write(&mut self, address: usize) -> Option<usize> {
+        let undone = self
+            .computer
+            .run_back_to_write(&mut self.history, address)?;
+        self.steps -= undone;
+        self.refresh_watchpoints();
+        Some(undone)
+    }
+
+    /// Total number of instructions executed under the debugger.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Returns every output produced since the last call.
+    pub fn take_outputs(&mut self) -> Vec<W> {
+        std::mem::take(&mut self.outputs)
+    }
+
+    /// Executes up to `count` instructions, stopping early on watchpoints, input requests, halts
+    /// and errors.
+    pub fn step(&mut self, count: usize) -> StopReason<W> {
+        self.run(Until::Steps(count))
+    }
+
+    /// Runs until a breakpoint, watchpoint, input request, halt or error.
+    pub fn resume(&mut self) -> StopReason<W> {
+        self.run(Until::Break)
+    }
+
+    /// Like `resume`, but also stops right after the next output.
+    pub fn run_to_output(&mut self) -> StopReason<W> {
+        self.run(Until::Output)
+    }
+
+    /// Runs until the program asks for input that isn't queued, ignoring breakpoints and
+    /// watchpoints.
+    pub fn run_to_input(&mut self) -> StopReason<W> {
+        self.run(Until::Input)
+    }
+
+    fn run(&mut self, until: Until) -> StopReason<W> {
+        let mut executed = 0;
+        loop {
+            if let Until::Steps(count) = until {
+                if executed == count {
+                    return StopReason::Stepped;
+                }
+            }
+            let ip = self.computer.ip();
+            let stops_at_breakpoints = until == Until::Break || until == Until::Output;
+            if executed > 0 && stops_at_breakpoints && self.breakpoints.contains(&ip) {
+                return StopReason::Breakpoint(ip);
+            }
+
+            let relative_base = self.computer.relative_base();
+            let result = match self.computer.step_with(&mut self.history) {
+                Ok(result) => result,
+                Err(err) => return StopReason::Error(err),
+            };
+            let output = match result {
+                StepResult::Stop => return StopReason::Halt,
+                StepResult::InputRequired => return StopReason::InputRequired,
+                StepResult::Output(value) => {
+                    self.outputs.push(value.clone());
+                    Some(value)
+                }
+                StepResult::Continue => None,
+            };
+            executed += 1;
+            self.steps += 1;
+
+            if let Some(reason) = self.check_watchpoints(relative_base) {
+                if until != Until::Input {
+                    return reason;
+                }
+            }
+            if let (Until::Output, Some(value)) = (until, output) {
+                return StopReason::Output(value);
+            }
+        }
+    }
+
+    fn refresh_watchpoints(&mut self) {
+        for (address, value) in self.watchpoints.iter_mut() {
+            *value = self.computer.memory().get(*address);
+        }
+    }
+
+    // Refreshes every watched value and reports the first one that changed.
+    fn check_watchpoints(&mut self, relative_base: i64) -> Option<StopReason<W>> {
+        let mut reason = None;
+        for (address, old) in self.watchpoints.iter_mut() {
+            let new = self.computer.memory().get(*address);
+            if new != *old {
+                let changed = std::mem::replace(old, new.clone());
+                reason = reason.or(Some(StopReason::Watchpoint {
+                    address: *address,
+                    old: changed,
+                    new,
+                }));
+            }
+        }
+
+        let new_base = self.computer.relative_base();
+        if self.watch_relative_base && new_base != relative_base {
+            reason = reason.or(Some(StopReason::RelativeBaseChanged {
+                old: relative_base,
+                new: new_base,
+            }));
+        }
+        reason
+    }
+}
@@ -0,0 +1,69 @@
+use crate::instruction::Header;
+use std::sync::Arc;
+
+// Instructions above this address are always decoded from memory.
+const CACHE_LIMIT: usize = 1 << 20;
+
+/// Decoded opcodes and parameter modes keyed by the address of their instruction word.
+///
+/// Parameter values are still read from memory on every step, so only a write to the instruction
+/// word itself has to go through `invalidate`. Clones share their entries until one of them
+/// invalidates a cached instruction, so a warmed up computer can be cloned cheaply for every run
+/// of a brute-force search. Only a cache that isn't shared learns new instructions.
+#[derive(Debug, Clone)]
+pub(crate) struct DecodeCache {
+    entries: Arc<Vec<Option<Header>>>,
+    enabled: bool,
+}
+
+impl DecodeCache {
+    pub(crate) fn new() -> DecodeCache {
+        DecodeCache {
+            entries: Arc::new(Vec::new()),
+            enabled: true,
+        }
+    }
+
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.entries = Arc::new(Vec::new());
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, ip: usize) -> Option<Header> {
+        self.entries.get(ip).copied().flatten()
+    }
+
+    #[inline]
+    pub(crate) fn insert(&mut self, ip: usize, header: Header) {
+        if !self.enabled || ip >= CACHE_LIMIT {
+            return;
+        }
+        if let Some(entries) = Arc::get_mut(&mut self.entries) {
+            if ip >= entries.len() {
+                entries.resize((ip + 1).next_power_of_two().max(64), None);
+            }
+            entries[ip] = Some(header);
+        }
+    }
+
+    /// Like `insert`, but also adds to a cache that is shared with clones.
+    pub(crate) fn insert_shared(&mut self, ip: usize, header: Header) {
+        if self.enabled && ip < CACHE_LIMIT {
+            Arc::make_mut(&mut self.entries);
+            self.insert(ip, header);
+        }
+    }
+
+    /// Forgets the instruction decoded from the word at `address`, if there is one.
+    #[inline]
+    pub(crate) fn invalidate(&mut self, address: usize) {
+        if let Some(Some(_)) = self.entries.get(address) {
+            Arc::make_mut(&mut self.entries)[address] = None;
+        }
+    }
+}
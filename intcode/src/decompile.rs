@@ -0,0 +1,984 @@
+//! Turns a program into structured pseudocode.
+//!
+//! Functions are found through calls, which compiled programs make by storing the return address
+//! at `[r+0]` and the arguments at `[r+1]`, `[r+2]`, ... before jumping. A function that starts
+//! with `ARB #n` gets a frame of `n` words, and relative operands are named by their offset from
+//! the relative base the function was entered with: `ret` is the return address, `local1` to
+//! `local{n-1}` hold the arguments and locals, and `out1`, `out2`, ... are the arguments of calls
+//! it makes, which is also where those calls leave their results. Other memory cells are named
+//! after their address, like `v382`. A call whose target the program writes into the jump first,
+//! or reads from memory, goes through a function pointer, like `call *local1(local2)`.
+//!
+//! ```text
+//! fn L0578(frame 4) {
+//!     local3 = local1 * 44
+//!     do {
+//!         local3 = local3 + local2
+//!         if (local3 >= 100) {
+//!             halt
+//!         }
+//!     } while (v381 != 0)
+//!     return
+//! }
+//! ```
+//!
+//! A conditional jump over a stretch of code becomes an `if`, a jump back to an earlier block a
+//! `do`/`while` loop, and a compare whose result is only used to branch is folded into the
+//! condition. Anything that doesn't fit these shapes is left as a `goto` to a label.
+
+use crate::{
+    cfg::{control_flow_graph, BasicBlock, ControlFlowGraph, EdgeKind},
+    disassemble::{label_name, return_address},
+    instruction::{Instruction, Opcode, Parameter, ParameterMode},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt,
+};
+
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub entry: usize,
+    /// Size of the frame set up by the function's first `ARB`, zero if it doesn't have one.
+    pub frame: i64,
+    /// Lines of pseudocode, indented by nesting depth.
+    pub body: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decompilation {
+    pub functions: Vec<Function>,
+}
+
+pub fn decompile(program: &[i64]) -> Decompilation {
+    let cfg = control_flow_graph(program);
+    let patched = cfg.patched_parameters();
+    let callees: BTreeSet<usize> = cfg
+        .blocks
+        .values()
+        .filter_map(|block| callee(block, &patched))
+        .filter(|callee| cfg.blocks.contains_key(callee))
+        .collect();
+    let mut entries = callees.clone();
+    if cfg.blocks.contains_key(&0) {
+        entries.insert(0);
+    }
+    let functions = entries
+        .into_iter()
+        .map(|entry| FunctionBuilder::new(&cfg, entry, &patched, &callees).build())
+        .collect();
+    Decompilation { functions }
+}
+
+impl fmt::Display for Decompilation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, function) in self.functions.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            writeln!(
+                f,
+                "fn {}(frame {}) {{",
+                label_name(function.entry),
+                function.frame
+            )?;
+            for line in &function.body {
+                writeln!(f, "{}", line)?;
+            }
+            writeln!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+// Whether a block ends in a call, having pushed the address to return to.
+fn pushes_return(block: &BasicBlock) -> bool {
+    block
+        .successors
+        .iter()
+        .any(|edge| edge.kind == EdgeKind::Return)
+}
+
+// The function a block calls, if it ends in a call to a known address. A call whose target the
+// program writes into the jump first goes through a function pointer instead.
+fn callee(block: &BasicBlock, patched: &BTreeSet<usize>) -> Option<usize> {
+    if !pushes_return(block) || patched.contains(&pointer_word(block)) {
+        return None;
+    }
+    block
+        .successors
+        .iter()
+        .find(|edge| edge.kind == EdgeKind::Jump)
+        .map(|edge| edge.target)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    Compare(String, &'static str, String),
+    NonZero(String, bool),
+    Always(bool),
+}
+
+impl Condition {
+    fn negate(&self) -> Condition {
+        match self {
+            Condition::Compare(lhs, op, rhs) => {
+                let op = match *op {
+                    "<" => ">=",
+                    ">=" => "<",
+                    "==" => "!=",
+                    _ => "==",
+                };
+                Condition::Compare(lhs.clone(), op, rhs.clone())
+            }
+            Condition::NonZero(value, nonzero) => Condition::NonZero(value.clone(), !nonzero),
+            Condition::Always(value) => Condition::Always(!value),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Condition::Compare(lhs, op, rhs) => write!(f, "{} {} {}", lhs, op, rhs),
+            Condition::NonZero(value, true) => write!(f, "{} != 0", value),
+            Condition::NonZero(value, false) => write!(f, "{} == 0", value),
+            Condition::Always(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+enum Open {
+    If { close: usize },
+    // Inner constructs have to close before the block jumping back to the loop header.
+    Loop { close: usize, back_edge: usize },
+}
+
+impl Open {
+    fn close(&self) -> usize {
+        match self {
+            Open::If { close } | Open::Loop { close, .. } => *close,
+        }
+    }
+
+    fn limit(&self) -> usize {
+        match self {
+            Open::If { close } => *close,
+            Open::Loop { back_edge, .. } => *back_edge,
+        }
+    }
+}
+
+enum Line {
+    Label(usize),
+    Code(usize, String),
+}
+
+struct FunctionBuilder<'a> {
+    cfg: &'a ControlFlowGraph,
+    // Parameter words the program writes to, see `ControlFlowGraph::patched_parameters`.
+    patched: &'a BTreeSet<usize>,
+    // Entries of the functions called directly, to name the function pointers the program
+    // stores.
+    functions: &'a BTreeSet<usize>,
+    entry: usize,
+    frame: i64,
+    members: BTreeMap<usize, &'a BasicBlock>,
+    // Loop header to the start and end of the block jumping back to it.
+    loops: BTreeMap<usize, (usize, usize)>,
+    lines: Vec<Line>,
+    open: Vec<Open>,
+    gotos: BTreeSet<usize>,
+}
+
+impl<'a> FunctionBuilder<'a> {
+    fn new(
+        cfg: &'a ControlFlowGraph,
+        entry: usize,
+        patched: &'a BTreeSet<usize>,
+        functions: &'a BTreeSet<usize>,
+    ) -> FunctionBuilder<'a> {
+        let mut members = BTreeMap::new();
+        let mut pending = VecDeque::new();
+        pending.push_back(entry);
+        while let Some(start) = pending.pop_front() {
+            let block = match cfg.blocks.get(&start) {
+                Some(block) if !members.contains_key(&start) => block,
+                _ => continue,
+            };
+            members.insert(start, block);
+            let calls = pushes_return(block);
+            for edge in &block.successors {
+                if !(calls && edge.kind == EdgeKind::Jump) {
+                    pending.push_back(edge.target);
+                }
+            }
+        }
+
+        let frame = match members[&entry].instructions.first() {
+            Some((_, instruction)) => prologue(instruction).unwrap_or(0),
+            None => 0,
+        };
+
+        let mut builder = FunctionBuilder {
+            cfg,
+            patched,
+            functions,
+            entry,
+            frame,
+            members,
+            loops: BTreeMap::new(),
+            lines: Vec::new(),
+            open: Vec::new(),
+            gotos: BTreeSet::new(),
+        };
+        for block in builder.members.values() {
+            if pushes_return(block) {
+                continue;
+            }
+            for edge in &block.successors {
+                let header = edge.target;
+                if edge.kind == EdgeKind::Jump
+                    && header <= block.start
+                    && builder.contiguous(header, block.end())
+                {
+                    let candidate = (block.start, block.end());
+                    let best = builder.loops.entry(header).or_insert(candidate);
+                    if candidate.1 > best.1 {
+                        *best = candidate;
+                    }
+                }
+            }
+        }
+        builder
+    }
+
+    // Whether the function's blocks cover every address from `start` up to `end`.
+    fn contiguous(&self, start: usize, end: usize) -> bool {
+        let mut address = start;
+        while address < end {
+            match self.members.get(&address) {
+                Some(block) => address = block.end(),
+                None => return false,
+            }
+        }
+        address == end
+    }
+
+    fn limit(&self) -> usize {
+        self.open.last().map_or(usize::MAX, Open::limit)
+    }
+
+    fn push(&mut self, line: String) {
+        let depth = self.open.len() + 1;
+        self.lines.push(Line::Code(depth, line));
+    }
+
+    fn close_until(&mut self, address: usize) {
+        while let Some(open) = self.open.pop() {
+            if open.close() > address {
+                self.open.push(open);
+                break;
+            }
+            let line = match open {
+                Open::If { .. } => "}".to_owned(),
+                // Only left open when the jump back had to become a `goto`.
+                Open::Loop { .. } => "} while (false)".to_owned(),
+            };
+            self.push(line);
+        }
+    }
+
+    fn build(mut self) -> Function {
+        let starts: Vec<usize> = self.members.keys().copied().collect();
+        for start in starts {
+            self.close_until(start);
+            self.lines.push(Line::Label(start));
+            if let Some(&(back_edge, close)) = self.loops.get(&start) {
+                if close <= self.limit() {
+                    self.push("do {".to_owned());
+                    self.open.push(Open::Loop { close, back_edge });
+                }
+            }
+            let block = self.members[&start];
+            self.block(block);
+        }
+        self.close_until(usize::MAX);
+
+        let gotos = self.gotos;
+        let body = self
+            .lines
+            .into_iter()
+            .filter_map(|line| match line {
+                Line::Label(address) if gotos.contains(&address) => {
+                    Some(format!("{}:", label_name(address)))
+                }
+                Line::Label(_) => None,
+                Line::Code(depth, text) => Some(format!("{}{}", INDENT.repeat(depth), text)),
+            })
+            .collect();
+        Function {
+            entry: self.entry,
+            frame: self.frame,
+            body,
+        }
+    }
+
+    fn block(&mut self, block: &BasicBlock) {
+        let instructions = &block.instructions;
+        let (last_address, last) = instructions[instructions.len() - 1];
+        let has_jump = last.opcode.is_jump();
+        let body = if has_jump {
+            &instructions[..instructions.len() - 1]
+        } else {
+            &instructions[..]
+        };
+
+        let deltas = self.deltas(block);
+        let jump_delta = deltas[deltas.len() - 1];
+        let returns = self.returns(block);
+
+        let mut statements: Vec<(usize, String)> = Vec::new();
+        for (index, (address, instruction)) in body.iter().enumerate() {
+            let is_prologue = block.start == self.entry && index == 0 && self.frame > 0;
+            let is_epilogue = returns
+                && immediate_adjustment(instruction)
+                    .is_some_and(|amount| deltas[index].checked_add(amount) == Some(0));
+            if is_prologue || is_epilogue {
+                continue;
+            }
+            if let Some(text) = self.statement(instruction, deltas[index]) {
+                statements.push((*address, text));
+            }
+        }
+
+        if !has_jump {
+            for (_, text) in statements {
+                self.push(text);
+            }
+            return;
+        }
+
+        let callee = match callee(block, self.patched) {
+            Some(callee) => Some(label_name(callee)),
+            // Calls through a function pointer, either written into the jump or read by it.
+            None if pushes_return(block) && self.patched.contains(&pointer_word(block)) => Some(
+                format!("*{}", self.pointer(block, body, &deltas, &mut statements)),
+            ),
+            None if block.unresolved && !returns && pushes_return(block) => Some(format!(
+                "*{}",
+                self.operand(&last.parameters()[1], jump_delta)
+            )),
+            None => None,
+        };
+        if let Some(callee) = callee {
+            let call = self.call(block, body, &deltas, &mut statements, callee);
+            for (_, text) in statements {
+                self.push(text);
+            }
+            self.push(call);
+            return;
+        }
+
+        // Fold a compare whose result only decides the jump.
+        let mut condition = self.jump_condition(&last, jump_delta);
+        if let (Some((address, instruction)), Condition::NonZero(value, nonzero)) =
+            (body.last(), &condition)
+        {
+            let index = body.len() - 1;
+            let compare = self
+                .compare(instruction, deltas[index], value)
+                .filter(|_| !self.read_later(block, value));
+            if let Some(compare) = compare {
+                if statements.last().map(|(at, _)| *at) == Some(*address) {
+                    statements.pop();
+                }
+                condition = if *nonzero { compare } else { compare.negate() };
+            }
+        }
+        for (_, text) in statements {
+            self.push(text);
+        }
+
+        if returns {
+            match condition {
+                Condition::Always(true) => self.push("return".to_owned()),
+                condition => self.push(format!("if ({}) return", condition)),
+            }
+            return;
+        }
+        if block.unresolved {
+            let target = self.operand(&last.parameters()[1], jump_delta);
+            match condition {
+                Condition::Always(true) => self.push(format!("goto *{}", target)),
+                condition => self.push(format!("if ({}) goto *{}", condition, target)),
+            }
+            return;
+        }
+        if condition == Condition::Always(false) {
+            return;
+        }
+
+        let target = block
+            .successors
+            .iter()
+            .find(|edge| edge.kind == EdgeKind::Jump)
+            .map(|edge| edge.target);
+        let target = match target {
+            Some(target) => target,
+            None => return,
+        };
+        let end = last_address + last.size();
+
+        if let Some(Open::Loop { back_edge, .. }) = self.open.last() {
+            if *back_edge == block.start && self.loops.get(&target) == Some(&(block.start, end)) {
+                self.open.pop();
+                self.push(format!("}} while ({})", condition));
+                return;
+            }
+        }
+        if condition != Condition::Always(true)
+            && target > end
+            && target <= self.limit()
+            && self.contiguous(end, target)
+        {
+            self.push(format!("if ({}) {{", condition.negate()));
+            self.open.push(Open::If { close: target });
+            return;
+        }
+        self.gotos.insert(target);
+        match condition {
+            Condition::Always(true) => self.push(format!("goto {}", label_name(target))),
+            condition => self.push(format!("if ({}) goto {}", condition, label_name(target))),
+        }
+    }
+
+    // Offset of the relative base from the one the function was entered with, at every
+    // instruction of `block`, so its operands can be named.
+    fn deltas(&self, block: &BasicBlock) -> Vec<i64> {
+        let mut delta = if block.start == self.entry {
+            0
+        } else {
+            self.frame
+        };
+        let mut deltas = Vec::with_capacity(block.instructions.len());
+        for (_, instruction) in &block.instructions {
+            deltas.push(delta);
+            if let Some(amount) = immediate_adjustment(instruction) {
+                // On overflow the computer stops right there, so the rest never runs.
+                delta = delta.saturating_add(amount);
+            }
+        }
+        deltas
+    }
+
+    // Whether `block` ends by jumping to its return address.
+    fn returns(&self, block: &BasicBlock) -> bool {
+        let (_, last) = block.instructions[block.instructions.len() - 1];
+        let deltas = self.deltas(block);
+        last.opcode.is_jump()
+            && block.unresolved
+            && self.operand(&last.parameters()[1], deltas[deltas.len() - 1]) == "ret"
+    }
+
+    // Whether `name` may be read after `from` ends, before anything overwrites it. Control flow
+    // the function can't follow counts as a read, except that nothing reads the frame after a
+    // return, and a call only reads memory outside the caller's frame and its arguments.
+    fn read_later(&self, from: &BasicBlock, name: &str) -> bool {
+        let global = name
+            .strip_prefix('v')
+            .and_then(|address| address.parse().ok());
+        if global.is_some_and(|address| self.block_local(address)) {
+            return false;
+        }
+        let outside_frame = global.is_some();
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![(from, false)];
+        while let Some((block, scan)) = pending.pop() {
+            if scan {
+                let mut overwritten = false;
+                for ((_, instruction), delta) in block.instructions.iter().zip(self.deltas(block)) {
+                    let params = instruction.parameters();
+                    let (read, written) = match params.split_last() {
+                        Some((written, read)) if instruction.opcode.writes() => {
+                            (read, Some(written))
+                        }
+                        _ => (params, None),
+                    };
+                    let named = |param: &Parameter| {
+                        param.mode != ParameterMode::Immediate && self.operand(param, delta) == name
+                    };
+                    if read.iter().any(named) {
+                        return true;
+                    }
+                    if written.is_some_and(named) {
+                        overwritten = true;
+                        break;
+                    }
+                }
+                if overwritten {
+                    continue;
+                }
+            }
+
+            let (_, last) = block.instructions[block.instructions.len() - 1];
+            if last.opcode == Opcode::Halt {
+                continue;
+            }
+            let calls = pushes_return(block);
+            if calls && (outside_frame || name.starts_with("out")) {
+                return true;
+            }
+            if block.unresolved && !calls && (outside_frame || !self.returns(block)) {
+                return true;
+            }
+            for edge in &block.successors {
+                if calls && edge.kind == EdgeKind::Jump {
+                    continue;
+                }
+                match self.members.get(&edge.target) {
+                    Some(next) if seen.insert(edge.target) => pending.push((next, true)),
+                    Some(_) => {}
+                    None => return true,
+                }
+            }
+        }
+        false
+    }
+
+    // Whether every read of the memory cell at `address` anywhere in the program comes after a
+    // write to it in the same block, like the scratch cells compilers keep compare results in.
+    fn block_local(&self, address: i64) -> bool {
+        let names =
+            |param: &Parameter| param.mode == ParameterMode::Position && param.value == address;
+        self.cfg.blocks.values().all(|block| {
+            let mut written = false;
+            for (_, instruction) in &block.instructions {
+                let params = instruction.parameters();
+                let (read, target) = match params.split_last() {
+                    Some((target, read)) if instruction.opcode.writes() => (read, Some(target)),
+                    _ => (params, None),
+                };
+                if !written && read.iter().any(names) {
+                    return false;
+                }
+                written |= target.is_some_and(names);
+            }
+            true
+        })
+    }
+
+    // The function pointer a call jumps through, when the program writes it into the jump's
+    // target. A store of it earlier in the block is taken out of `statements` and named by the
+    // value it stores, unless the block changes that value before the call.
+    fn pointer(
+        &self,
+        block: &BasicBlock,
+        body: &[(usize, Instruction)],
+        deltas: &[i64],
+        statements: &mut Vec<(usize, String)>,
+    ) -> String {
+        let word = pointer_word(block);
+        let stores = body.iter().rposition(|(_, instruction)| {
+            let target = instruction.parameters().last();
+            instruction.opcode.writes()
+                && target.is_some_and(|target| {
+                    target.mode == ParameterMode::Position && target.value == word as i64
+                })
+        });
+        let index = match stores {
+            Some(index) => index,
+            None => return format!("v{}", word),
+        };
+        let (address, instruction) = &body[index];
+        let value = match self.value(instruction, deltas[index]) {
+            Some(value) => value,
+            None => return format!("v{}", word),
+        };
+        let changed =
+            body[index + 1..]
+                .iter()
+                .zip(&deltas[index + 1..])
+                .any(|((_, later), delta)| {
+                    later.opcode.writes()
+                        && reads(
+                            &value,
+                            &self
+                                .operand(&later.parameters()[later.parameters().len() - 1], *delta),
+                        )
+                });
+        if changed {
+            return format!("v{}", word);
+        }
+        statements.retain(|(at, _)| at != address);
+        value
+    }
+
+    // Renders a call, taking the argument stores right before it out of `statements`.
+    fn call(
+        &self,
+        block: &BasicBlock,
+        body: &[(usize, Instruction)],
+        deltas: &[i64],
+        statements: &mut Vec<(usize, String)>,
+        callee: String,
+    ) -> String {
+        let mut arguments: BTreeMap<i64, String> = BTreeMap::new();
+        let mut folded = BTreeSet::new();
+        let mut return_push = None;
+        for (index, (address, instruction)) in body.iter().enumerate().rev() {
+            let params = instruction.parameters();
+            if return_address(instruction) == Some(block.end())
+                && params[2].mode == ParameterMode::Relative
+            {
+                return_push = Some(*address);
+                continue;
+            }
+            let slot = match self.out_slot(instruction, deltas[index]) {
+                Some(slot) if !arguments.contains_key(&slot) => slot,
+                _ => break,
+            };
+            // Moving the store into the call would change what a later argument reads.
+            let name = format!("out{}", slot);
+            if arguments.values().any(|value| reads(value, &name)) {
+                break;
+            }
+            let value = match self.value(instruction, deltas[index]) {
+                Some(value) => value,
+                None => break,
+            };
+            arguments.insert(slot, value);
+            folded.insert(*address);
+        }
+        if !arguments.keys().copied().eq(1..=arguments.len() as i64) {
+            arguments.clear();
+            folded.clear();
+        }
+        folded.extend(return_push);
+        statements.retain(|(address, _)| !folded.contains(address));
+        let arguments: Vec<String> = arguments.into_values().collect();
+        format!("call {}({})", callee, arguments.join(", "))
+    }
+
+    // The outgoing argument slot an instruction writes to, if it writes to one.
+    fn out_slot(&self, instruction: &Instruction, delta: i64) -> Option<i64> {
+        if !instruction.opcode.writes() {
+            return None;
+        }
+        let target = instruction.parameters()[instruction.parameters().len() - 1];
+        if target.mode != ParameterMode::Relative {
+            return None;
+        }
+        let offset = target.value.checked_add(delta)?;
+        if offset > self.frame {
+            Some(offset - self.frame)
+        } else {
+            None
+        }
+    }
+
+    fn operand(&self, parameter: &Parameter, delta: i64) -> String {
+        match parameter.mode {
+            ParameterMode::Immediate => parameter.value.to_string(),
+            ParameterMode::Position => format!("v{}", parameter.value),
+            ParameterMode::Relative => {
+                let offset = match parameter.value.checked_add(delta) {
+                    Some(offset) => offset,
+                    None => return format!("frame[{} + {}]", delta, parameter.value),
+                };
+                if offset == 0 {
+                    "ret".to_owned()
+                } else if offset > 0 && offset < self.frame {
+                    format!("local{}", offset)
+                } else if offset >= self.frame && offset > 0 {
+                    format!("out{}", offset - self.frame)
+                } else {
+                    format!("frame[{}]", offset)
+                }
+            }
+        }
+    }
+
+    // The value an arithmetic or compare instruction stores.
+    fn value(&self, instruction: &Instruction, delta: i64) -> Option<String> {
+        let params = instruction.parameters();
+        let operand = |index: usize| self.operand(&params[index], delta);
+        let immediate = |index: usize| {
+            Some(params[index].value).filter(|_| params[index].mode == ParameterMode::Immediate)
+        };
+        if let Some(address) = return_address(instruction) {
+            if self.functions.contains(&address) {
+                return Some(label_name(address));
+            }
+        }
+        let text = match instruction.opcode {
+            // Constants are only folded when the computer wouldn't overflow computing them.
+            Opcode::Add => match (immediate(0), immediate(1)) {
+                (Some(a), Some(b)) if a.checked_add(b).is_some() => (a + b).to_string(),
+                (_, Some(0)) => operand(0),
+                (Some(0), _) => operand(1),
+                (_, Some(b)) if b < 0 && b != i64::MIN => format!("{} - {}", operand(0), -b),
+                (Some(a), _) if a < 0 && a != i64::MIN => format!("{} - {}", operand(1), -a),
+                _ => format!("{} + {}", operand(0), operand(1)),
+            },
+            Opcode::Mul => match (immediate(0), immediate(1)) {
+                (Some(a), Some(b)) if a.checked_mul(b).is_some() => (a * b).to_string(),
+                (_, Some(0)) | (Some(0), _) => "0".to_owned(),
+                (_, Some(1)) => operand(0),
+                (Some(1), _) => operand(1),
+                (_, Some(-1)) => format!("-{}", operand(0)),
+                (Some(-1), _) => format!("-{}", operand(1)),
+                _ => format!("{} * {}", operand(0), operand(1)),
+            },
+            Opcode::LessThan => format!("{} < {}", operand(0), operand(1)),
+            Opcode::Equals => format!("{} == {}", operand(0), operand(1)),
+            Opcode::Input => "input()".to_owned(),
+            _ => return None,
+        };
+        Some(text)
+    }
+
+    fn statement(&self, instruction: &Instruction, delta: i64) -> Option<String> {
+        let params = instruction.parameters();
+        let text = match instruction.opcode {
+            Opcode::Input => format!("{} = input()", self.operand(&params[0], delta)),
+            Opcode::Output => format!("output({})", self.operand(&params[0], delta)),
+            Opcode::AdjustRelativeBase => format!("rb += {}", self.operand(&params[0], delta)),
+            Opcode::Halt => "halt".to_owned(),
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => return None,
+            _ => format!(
+                "{} = {}",
+                self.operand(&params[2], delta),
+                self.value(instruction, delta)?
+            ),
+        };
+        Some(text)
+    }
+
+    fn jump_condition(&self, jump: &Instruction, delta: i64) -> Condition {
+        let condition = jump.parameters()[0];
+        let nonzero = jump.opcode == Opcode::JumpIfTrue;
+        if condition.mode == ParameterMode::Immediate {
+            Condition::Always((condition.value != 0) == nonzero)
+        } else {
+            Condition::NonZero(self.operand(&condition, delta), nonzero)
+        }
+    }
+
+    // The comparison a compare instruction makes, if it stores its result in `target`.
+    fn compare(&self, instruction: &Instruction, delta: i64, target: &str) -> Option<Condition> {
+        let op = match instruction.opcode {
+            Opcode::LessThan => "<",
+            Opcode::Equals => "==",
+            _ => return None,
+        };
+        let params = instruction.parameters();
+        if self.operand(&params[2], delta) != target {
+            return None;
+        }
+        Some(Condition::Compare(
+            self.operand(&params[0], delta),
+            op,
+            self.operand(&params[1], delta),
+        ))
+    }
+}
+
+// Whether `name` appears in `text` as a whole word.
+fn reads(text: &str, name: &str) -> bool {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| word == name)
+}
+
+// Address of the target parameter of the jump ending `block`.
+fn pointer_word(block: &BasicBlock) -> usize {
+    let (address, _) = block.instructions[block.instructions.len() - 1];
+    address + 2
+}
+
+fn immediate_adjustment(instruction: &Instruction) -> Option<i64> {
+    let offset = instruction.parameters().first()?;
+    if instruction.opcode == Opcode::AdjustRelativeBase && offset.mode == ParameterMode::Immediate {
+        Some(offset.value)
+    } else {
+        None
+    }
+}
+
+fn prologue(instruction: &Instruction) -> Option<i64> {
+    immediate_adjustment(instruction).filter(|amount| *amount > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assemble::assemble, computer::parse_program};
+
+    fn source(program: &[i64]) -> String {
+        decompile(program).to_string()
+    }
+
+    #[test]
+    fn only_folds_constants_that_dont_overflow() {
+        assert_eq!(
+            source(&[1101, 9223372036854775807, 1, 5, 99]),
+            "fn L0000(frame 0) {\n    v5 = 9223372036854775807 + 1\n    halt\n}\n"
+        );
+        assert_eq!(
+            source(&[1001, 5, i64::MIN, 5, 99]),
+            "fn L0000(frame 0) {\n    v5 = v5 + -9223372036854775808\n    halt\n}\n"
+        );
+        assert_eq!(
+            source(&[1102, 4611686018427387904, 4, 5, 99]),
+            "fn L0000(frame 0) {\n    v5 = 4611686018427387904 * 4\n    halt\n}\n"
+        );
+        assert_eq!(
+            source(&[1101, 2, -3, 5, 1102, -1, 7, 9, 1001, 5, -3, 5, 99]),
+            "fn L0000(frame 0) {\n    v5 = -1\n    v9 = -7\n    v5 = v5 - 3\n    halt\n}\n"
+        );
+    }
+
+    #[test]
+    fn folds_compares_only_the_branch_reads() {
+        assert_eq!(
+            source(&[1007, 20, 5, 21, 1005, 21, 10, 4, 20, 99, 4, 20, 99]),
+            "fn L0000(frame 0) {
+    if (v20 >= 5) {
+        output(v20)
+        halt
+    }
+    output(v20)
+    halt
+}
+"
+        );
+        assert_eq!(
+            source(&[1007, 20, 5, 21, 1005, 21, 10, 4, 21, 99, 4, 21, 99]),
+            "fn L0000(frame 0) {
+    v21 = v20 < 5
+    if (v21 == 0) {
+        output(v21)
+        halt
+    }
+    output(v21)
+    halt
+}
+"
+        );
+    }
+
+    #[test]
+    fn recognises_calls_and_returns() {
+        let program = assemble(
+            "
+            IN [x]
+            ARB #stack
+            JZ [x], #done
+            ADD [x], #0 -> [r+1]
+            ADD #back, #0 -> [r]
+            JNZ #1, #square
+    back:   OUT [r+1]
+    done:   HLT
+    square: ARB #2
+            MUL [r-1], [r-1] -> [r-1]
+            ARB #-2
+            JNZ #1, [r]
+    x:      DATA 0
+    stack:  DATA 0, 0, 0, 0",
+        )
+        .unwrap();
+        assert_eq!(
+            source(&program),
+            "fn L0000(frame 0) {
+    v32 = input()
+    rb += 33
+    if (v32 != 0) {
+        call L0021(v32)
+        output(out1)
+    }
+    halt
+}
+
+fn L0021(frame 2) {
+    local1 = local1 * local1
+    return
+}
+"
+        );
+    }
+
+    #[test]
+    fn turns_jumps_back_into_loops() {
+        let program = assemble(
+            "
+            IN [n]
+    loop:   OUT [n]
+            ADD [n], #-1 -> [n]
+            LT #0, [n] -> [t]
+            JNZ [t], #loop
+            HLT
+    n:      DATA 0
+    t:      DATA 0",
+        )
+        .unwrap();
+        assert_eq!(
+            source(&program),
+            "fn L0000(frame 0) {
+    v16 = input()
+    do {
+        output(v16)
+        v16 = v16 - 1
+    } while (0 < v16)
+    halt
+}
+"
+        );
+    }
+
+    #[test]
+    fn calls_through_pointers_written_into_the_jump() {
+        let program = assemble(
+            "
+            IN [x]
+            ADD #double, #0 -> [call+2]
+            ADD [x], #0 -> [r+1]
+            ADD #back, #0 -> [r]
+    call:   JNZ #1, #0
+    back:   OUT [r+1]
+            HLT
+    double: ARB #2
+            MUL [r-1], #2 -> [r-1]
+            ARB #-2
+            JNZ #1, [r]
+    x:      DATA 0",
+        )
+        .unwrap();
+        assert_eq!(
+            source(&program),
+            "fn L0000(frame 0) {
+    v31 = input()
+    call *20(v31)
+    output(out1)
+    halt
+}
+"
+        );
+
+        // L0225 calls the function passed as its first argument with the other three.
+        let source = source(&parse_program(include_str!("../../day19/input.txt")));
+        assert!(source.contains(
+            "fn L0225(frame 5) {
+    call *local1(local2, local3, local4)
+    local1 = out1
+    return
+}
+"
+        ));
+        assert!(source.contains("\n    call L0225(L0259, v221, v221)\n"));
+        assert!(source.contains("\n    call L0225(L0225, L0225, L0259, v222)\n"));
+        // The program writes to the target of this call later on, so it can't be trusted.
+        assert!(source.contains("\n    call *v132()\n"));
+    }
+}
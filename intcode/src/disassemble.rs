@@ -0,0 +1,346 @@
+use crate::instruction::{Instruction, Opcode, Parameter, ParameterMode};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+// Maximum number of words shown on a single data line.
+const DATA_WORDS_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    Instruction {
+        address: usize,
+        instruction: Instruction,
+    },
+    Data {
+        address: usize,
+        values: Vec<i64>,
+    },
+}
+
+/// An annotated listing of a program, split into instructions and likely data regions.
+#[derive(Debug, Clone)]
+pub struct Disassembly {
+    pub entries: Vec<Entry>,
+    pub labels: BTreeSet<usize>,
+}
+
+/// Disassembles `program` by following control flow from address 0.
+///
+/// Anything that can't be reached by following jumps with immediate targets (or through return
+/// addresses pushed right before a call) is treated as data.
+pub fn disassemble(program: &[i64]) -> Disassembly {
+    let (code, labels) = find_code(program);
+
+    let mut entries = Vec::new();
+    let mut address = 0;
+    while address < program.len() {
+        if let Some(instruction) = code.get(&address) {
+            entries.push(Entry::Instruction {
+                address,
+                instruction: *instruction,
+            });
+            address += instruction.size();
+            continue;
+        }
+
+        let start = address;
+        let mut values = Vec::new();
+        while address < program.len()
+            && !code.contains_key(&address)
+            && values.len() < DATA_WORDS_PER_LINE
+            && (address == start || !labels.contains(&address))
+        {
+            values.push(program[address]);
+            address += 1;
+        }
+        entries.push(Entry::Data {
+            address: start,
+            values,
+        });
+    }
+
+    Disassembly { entries, labels }
+}
+
+pub(crate) fn find_code(program: &[i64]) -> (BTreeMap<usize, Instruction>, BTreeSet<usize>) {
+    let mut code = BTreeMap::new();
+    let mut covered = vec![false; program.len()];
+    let mut labels = BTreeSet::new();
+    let mut pending = vec![0];
+    // Return addresses already queued, which stay uncovered when they don't decode.
+    let mut returns = BTreeSet::new();
+
+    while !pending.is_empty() {
+        while let Some(mut address) = pending.pop() {
+            while address < program.len() && !covered[address] {
+                let instruction =
+                    match Instruction::decode(address, |a| program.get(a).copied().unwrap_or(0)) {
+                        Ok(instruction) => instruction,
+                        Err(_) => break,
+                    };
+                let end = address + instruction.size();
+                if end > program.len() || covered[address..end].iter().any(|c| *c) {
+                    break;
+                }
+                covered[address..end].iter_mut().for_each(|c| *c = true);
+                code.insert(address, instruction);
+
+                if instruction.opcode == Opcode::Halt {
+                    break;
+                }
+                if instruction.opcode.is_jump() {
+                    let (taken, falls_through) = jump_outcomes(&instruction);
+                    if let Some(target) = jump_target(&instruction) {
+                        if taken && target < program.len() {
+                            labels.insert(target);
+                            pending.push(target);
+                        }
+                    }
+                    if !falls_through {
+                        break;
+                    }
+                }
+                address = end;
+            }
+        }
+
+        // Calls push their return address right before jumping, so the code after a call is only
+        // reachable through that pushed value.
+        for (address, instruction) in &code {
+            if let Some(target) = return_address(instruction) {
+                if target != *address
+                    && target < program.len()
+                    && !covered[target]
+                    && follows_jump(&code, target)
+                    && returns.insert(target)
+                {
+                    labels.insert(target);
+                    pending.push(target);
+                }
+            }
+        }
+    }
+
+    (code, labels)
+}
+
+// Returns whether a jump can be taken and whether it can fall through, using the condition when
+// it is an immediate.
+pub(crate) fn jump_outcomes(instruction: &Instruction) -> (bool, bool) {
+    let condition = instruction.parameters()[0];
+    if condition.mode != ParameterMode::Immediate {
+        return (true, true);
+    }
+    let jumps = match instruction.opcode {
+        Opcode::JumpIfTrue => condition.value != 0,
+        _ => condition.value == 0,
+    };
+    (jumps, !jumps)
+}
+
+pub(crate) fn jump_target(instruction: &Instruction) -> Option<usize> {
+    let target = instruction.parameters()[1];
+    if target.mode == ParameterMode::Immediate && target.value >= 0 {
+        Some(target.value as usize)
+    } else {
+        None
+    }
+}
+
+// Recognises `ADD #x, #0` and `MUL #x, #1` (in either order), which is how programs store a
+// constant such as a return address.
+pub(crate) fn return_address(instruction: &Instruction) -> Option<usize> {
+    let neutral = match instruction.opcode {
+        Opcode::Add => 0,
+        Opcode::Mul => 1,
+        _ => return None,
+    };
+    let params = instruction.parameters();
+    if params[0].mode != ParameterMode::Immediate || params[1].mode != ParameterMode::Immediate {
+        return None;
+    }
+    let value = if params[1].value == neutral {
+        params[0].value
+    } else if params[0].value == neutral {
+        params[1].value
+    } else {
+        return None;
+    };
+    if value >= 0 {
+        Some(value as usize)
+    } else {
+        None
+    }
+}
+
+fn follows_jump(code: &BTreeMap<usize, Instruction>, address: usize) -> bool {
+    code.range(..address)
+        .next_back()
+        .is_some_and(|(start, instruction)| {
+            instruction.opcode.is_jump() && start + instruction.size() == address
+        })
+}
+
+// The words of an instruction as they appear in the program.
+pub(crate) fn raw_words(instruction: &Instruction) -> String {
+    std::iter::once(instruction.word)
+        .chain(instruction.parameters().iter().map(|p| p.value))
+        .map(|word| word.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn label_name(address: usize) -> String {
+    format!("L{:04}", address)
+}
+
+impl Disassembly {
+    fn format_parameter(&self, instruction: &Instruction, index: usize) -> String {
+        let parameter: Parameter = instruction.parameters()[index];
+        let is_target = (instruction.opcode.is_jump() && index == 1)
+            || (index < 2 && return_address(instruction) == Some(parameter.value as usize));
+        if parameter.mode == ParameterMode::Immediate
+            && is_target
+            && parameter.value >= 0
+            && self.labels.contains(&(parameter.value as usize))
+        {
+            format!("#{}", label_name(parameter.value as usize))
+        } else {
+            parameter.to_string()
+        }
+    }
+
+    /// Formats an instruction like its `Display` impl, but with jump targets replaced by labels.
+    pub fn format_instruction(&self, instruction: &Instruction) -> String {
+        let count = instruction.parameters().len();
+        let reads = if instruction.opcode.writes() {
+            count - 1
+        } else {
+            count
+        };
+        let mut text = instruction.opcode.mnemonic().to_owned();
+        for index in 0..reads {
+            text.push_str(if index == 0 { " " } else { ", " });
+            text.push_str(&self.format_parameter(instruction, index));
+        }
+        if reads < count {
+            text.push_str(" -> ");
+            text.push_str(&self.format_parameter(instruction, reads));
+        }
+        text
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            match entry {
+                Entry::Instruction {
+                    address,
+                    instruction,
+                } => {
+                    if self.labels.contains(address) {
+                        writeln!(f, "{}:", label_name(*address))?;
+                    }
+                    writeln!(
+                        f,
+                        "{:>5}  {:<24} {}",
+                        address,
+                        raw_words(instruction),
+                        self.format_instruction(instruction)
+                    )?;
+                }
+                Entry::Data { address, values } => {
+                    if self.labels.contains(address) {
+                        writeln!(f, "{}:", label_name(*address))?;
+                    }
+                    let values = values
+                        .iter()
+                        .map(|value| value.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    writeln!(f, "{:>5}  {:<24} DATA {}", address, "", values)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreachable_words_are_data() {
+        let listing = disassemble(&[
+            1105, 1, 7, 1, 2, 3, 4, 104, 5, 99, 1, 2, 3, 4, 5, 6, 7, 8, 9,
+        ]);
+        assert_eq!(
+            listing.to_string(),
+            "    0  1105,1,7                 JNZ #1, #L0007
+    3                           DATA 1, 2, 3, 4
+L0007:
+    7  104,5                    OUT #5
+    9  99                       HLT
+   10                           DATA 1, 2, 3, 4, 5, 6, 7, 8
+   18                           DATA 9
+"
+        );
+        assert_eq!(listing.labels, [7].iter().copied().collect());
+    }
+
+    #[test]
+    fn return_addresses_are_code() {
+        // Pushes a return address and calls the function at 10, which returns through it.
+        let program = [21101, 7, 0, 0, 1105, 1, 10, 104, 1, 99, 2105, 1, 0, -1];
+        assert_eq!(
+            disassemble(&program).to_string(),
+            "    0  21101,7,0,0              ADD #L0007, #0 -> [r+0]
+    4  1105,1,10                JNZ #1, #L0010
+L0007:
+    7  104,1                    OUT #1
+    9  99                       HLT
+L0010:
+   10  2105,1,0                 JNZ #1, [r+0]
+   13                           DATA -1
+"
+        );
+    }
+
+    #[test]
+    fn labels_start_data_lines() {
+        // The jump lands on a word that isn't an instruction.
+        let listing = disassemble(&[1006, 9, 6, 99, 5, 5, 42, 5, 5, 0]);
+        assert_eq!(
+            listing.to_string(),
+            "    0  1006,9,6                 JZ [9], #L0006
+    3  99                       HLT
+    4                           DATA 5, 5
+L0006:
+    6                           DATA 42, 5, 5, 0
+"
+        );
+    }
+
+    #[test]
+    fn targets_outside_the_program_are_not_labels() {
+        let listing = disassemble(&[1106, 0, 1234, 99]);
+        assert_eq!(
+            listing.to_string(),
+            "    0  1106,0,1234              JZ #0, #1234
+    3                           DATA 99
+"
+        );
+        assert!(listing.labels.is_empty());
+    }
+
+    #[test]
+    fn label_names_are_padded() {
+        assert_eq!(label_name(7), "L0007");
+        assert_eq!(label_name(1234), "L1234");
+        assert_eq!(label_name(123_456), "L123456");
+    }
+}
@@ -0,0 +1,216 @@
+use crate::word::Word;
+use std::{error::Error, fmt};
+
+/// Errors raised while executing an Intcode program.
+///
+/// `ip` is the address of the faulting instruction and `opcode` is the raw instruction word at that
+/// address, parameter modes included. The computer is left pointing at the faulting instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IntcodeError<W = i64> {
+    InvalidOpcode {
+        ip: usize,
+        opcode: W,
+    },
+    InvalidParameterMode {
+        ip: usize,
+        opcode: W,
+        mode: i64,
+    },
+    ImmediateWrite {
+        ip: usize,
+        opcode: W,
+    },
+    NegativeAddress {
+        ip: usize,
+        opcode: W,
+        address: W,
+    },
+    /// The address is too large to index memory with on this platform.
+    AddressTooLarge {
+        ip: usize,
+        opcode: W,
+        address: W,
+    },
+    /// The result of an `ADD` or `MUL`, or the new relative base, doesn't fit the word type.
+    Overflow {
+        ip: usize,
+        opcode: W,
+    },
+}
+
+impl<W: Word> fmt::Display for IntcodeError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::InvalidOpcode { ip, opcode } => {
+                write!(f, "unexpected opcode {} at ip {}", opcode, ip)
+            }
+            IntcodeError::InvalidParameterMode { ip, opcode, mode } => write!(
+                f,
+                "invalid parameter mode {} in instruction {} at ip {}",
+                mode, opcode, ip
+            ),
+            IntcodeError::ImmediateWrite { ip, opcode } => write!(
+                f,
+                "instruction {} at ip {} writes to an immediate parameter",
+                opcode, ip
+            ),
+            IntcodeError::NegativeAddress {
+                ip,
+                opcode,
+                address,
+            } => write!(
+                f,
+                "instruction {} at ip {} accesses negative address {}",
+                opcode, ip, address
+            ),
+            IntcodeError::AddressTooLarge {
+                ip,
+                opcode,
+                address,
+            } => write!(
+                f,
+                "instruction {} at ip {} accesses address {}, which is too large",
+                opcode, ip, address
+            ),
+            IntcodeError::Overflow { ip, opcode } => write!(
+                f,
+                "instruction {} at ip {} overflowed the word type",
+                opcode, ip
+            ),
+        }
+    }
+}
+
+impl<W: Word> Error for IntcodeError<W> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::{Computer, ComputerState};
+
+    // Runs `program` to the first error, checking the computer stays at the faulting instruction.
+    fn error<W: Word>(program: Vec<W>) -> IntcodeError<W> {
+        let mut computer = Computer::new(program);
+        let err = computer.execute().unwrap_err();
+        let ip = match &err {
+            IntcodeError::InvalidOpcode { ip, .. }
+            | IntcodeError::InvalidParameterMode { ip, .. }
+            | IntcodeError::ImmediateWrite { ip, .. }
+            | IntcodeError::NegativeAddress { ip, .. }
+            | IntcodeError::AddressTooLarge { ip, .. }
+            | IntcodeError::Overflow { ip, .. } => *ip,
+        };
+        assert_eq!(computer.ip(), ip);
+        err
+    }
+
+    fn i64_error(program: Vec<i64>) -> IntcodeError {
+        error(program)
+    }
+
+    #[test]
+    fn invalid_opcode() {
+        assert_eq!(
+            i64_error(vec![1101, 1, 2, 5, 42, 0]),
+            IntcodeError::InvalidOpcode { ip: 4, opcode: 42 }
+        );
+        assert_eq!(
+            i64_error(vec![-1]),
+            IntcodeError::InvalidOpcode { ip: 0, opcode: -1 }
+        );
+    }
+
+    #[test]
+    fn invalid_parameter_mode() {
+        assert_eq!(
+            i64_error(vec![109, 1, 301, 0, 0, 0, 99]),
+            IntcodeError::InvalidParameterMode {
+                ip: 2,
+                opcode: 301,
+                mode: 3
+            }
+        );
+    }
+
+    #[test]
+    fn immediate_write() {
+        assert_eq!(
+            i64_error(vec![109, 1, 11107, 1, 2, 3, 99]),
+            IntcodeError::ImmediateWrite {
+                ip: 2,
+                opcode: 11107,
+            }
+        );
+    }
+
+    #[test]
+    fn negative_address() {
+        assert_eq!(
+            i64_error(vec![1, -3, 0, 0, 99]),
+            IntcodeError::NegativeAddress {
+                ip: 0,
+                opcode: 1,
+                address: -3
+            }
+        );
+        assert_eq!(
+            i64_error(vec![109, -5, 21101, 1, 1, 2, 99]),
+            IntcodeError::NegativeAddress {
+                ip: 2,
+                opcode: 21101,
+                address: -3
+            }
+        );
+        assert_eq!(
+            i64_error(vec![1105, 1, -1]),
+            IntcodeError::NegativeAddress {
+                ip: 0,
+                opcode: 1105,
+                address: -1
+            }
+        );
+    }
+
+    #[test]
+    fn address_too_large() {
+        let address = 1i128 << 70;
+        assert_eq!(
+            error(vec![1101, 1, 1, 7, 4, address, 99, 0]),
+            IntcodeError::AddressTooLarge {
+                ip: 4,
+                opcode: 4,
+                address
+            }
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(
+            i64_error(vec![1101, i64::MAX, 1, 0, 99]),
+            IntcodeError::Overflow {
+                ip: 0,
+                opcode: 1101
+            }
+        );
+        assert_eq!(
+            i64_error(vec![1102, 1 << 62, -4, 0, 99]),
+            IntcodeError::Overflow {
+                ip: 0,
+                opcode: 1102
+            }
+        );
+        assert_eq!(
+            i64_error(vec![109, i64::MIN, 109, -1, 99]),
+            IntcodeError::Overflow { ip: 2, opcode: 109 }
+        );
+    }
+
+    #[test]
+    fn faulting_instruction_can_be_retried() {
+        let mut computer = Computer::new(vec![1i64, -1, 0, 0, 4, 0, 99]);
+        assert!(computer.execute().is_err());
+        computer.set_memory(1, 0);
+        assert_eq!(computer.execute(), Ok(ComputerState::Output(2)));
+    }
+}
@@ -0,0 +1,269 @@
+use crate::{error::IntcodeError, word::Word};
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Add,
+    Mul,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    AdjustRelativeBase,
+    Halt,
+}
+
+impl Opcode {
+    pub const ALL: [Opcode; 10] = [
+        Opcode::Add,
+        Opcode::Mul,
+        Opcode::Input,
+        Opcode::Output,
+        Opcode::JumpIfTrue,
+        Opcode::JumpIfFalse,
+        Opcode::LessThan,
+        Opcode::Equals,
+        Opcode::AdjustRelativeBase,
+        Opcode::Halt,
+    ];
+
+    /// Decodes the opcode from the last two digits of an instruction word.
+    #[inline]
+    pub fn from_int(value: i64) -> Option<Opcode> {
+        match value % 100 {
+            1 => Some(Opcode::Add),
+            2 => Some(Opcode::Mul),
+            3 => Some(Opcode::Input),
+            4 => Some(Opcode::Output),
+            5 => Some(Opcode::JumpIfTrue),
+            6 => Some(Opcode::JumpIfFalse),
+            7 => Some(Opcode::LessThan),
+            8 => Some(Opcode::Equals),
+            9 => Some(Opcode::AdjustRelativeBase),
+            99 => Some(Opcode::Halt),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(self) -> i64 {
+        match self {
+            Opcode::Add => 1,
+            Opcode::Mul => 2,
+            Opcode::Input => 3,
+            Opcode::Output => 4,
+            Opcode::JumpIfTrue => 5,
+            Opcode::JumpIfFalse => 6,
+            Opcode::LessThan => 7,
+            Opcode::Equals => 8,
+            Opcode::AdjustRelativeBase => 9,
+            Opcode::Halt => 99,
+        }
+    }
+
+    #[inline]
+    pub fn parameter_count(self) -> usize {
+        match self {
+            Opcode::Add | Opcode::Mul | Opcode::LessThan | Opcode::Equals => 3,
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => 2,
+            Opcode::Input | Opcode::Output | Opcode::AdjustRelativeBase => 1,
+            Opcode::Halt => 0,
+        }
+    }
+
+    /// Whether the last parameter of this opcode is a write target.
+    #[inline]
+    pub fn writes(self) -> bool {
+        matches!(
+            self,
+            Opcode::Add | Opcode::Mul | Opcode::Input | Opcode::LessThan | Opcode::Equals
+        )
+    }
+
+    pub fn is_jump(self) -> bool {
+        self == Opcode::JumpIfTrue || self == Opcode::JumpIfFalse
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Opcode::Add => "ADD",
+            Opcode::Mul => "MUL",
+            Opcode::Input => "IN",
+            Opcode::Output => "OUT",
+            Opcode::JumpIfTrue => "JNZ",
+            Opcode::JumpIfFalse => "JZ",
+            Opcode::LessThan => "LT",
+            Opcode::Equals => "EQ",
+            Opcode::AdjustRelativeBase => "ARB",
+            Opcode::Halt => "HLT",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ParameterMode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl ParameterMode {
+    pub fn as_int(self) -> i64 {
+        match self {
+            ParameterMode::Position => 0,
+            ParameterMode::Immediate => 1,
+            ParameterMode::Relative => 2,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Parameter<W = i64> {
+    pub mode: ParameterMode,
+    pub value: W,
+}
+
+impl<W: Word> fmt::Display for Parameter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mode {
+            ParameterMode::Position => write!(f, "[{}]", self.value),
+            ParameterMode::Immediate => write!(f, "#{}", self.value),
+            ParameterMode::Relative if self.value < W::zero() => write!(f, "[r{}]", self.value),
+            ParameterMode::Relative => write!(f, "[r+{}]", self.value),
+        }
+    }
+}
+
+/// A single decoded instruction. Only the first `opcode.parameter_count()` parameters are used.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Instruction<W = i64> {
+    pub word: W,
+    pub opcode: Opcode,
+    parameters: [Parameter<W>; 3],
+}
+
+/// The part of an instruction that only depends on its opcode word, which is what
+/// `Computer` caches per address.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Header {
+    pub(crate) opcode: Opcode,
+    pub(crate) modes: [ParameterMode; 3],
+}
+
+impl Header {
+    /// Decodes the opcode and parameter modes of the instruction word found at `ip`.
+    #[inline]
+    pub(crate) fn decode<W: Word>(ip: usize, word: &W) -> Result<Header, IntcodeError<W>> {
+        let invalid = || IntcodeError::InvalidOpcode {
+            ip,
+            opcode: word.clone(),
+        };
+        let small = word.to_i64().ok_or_else(invalid)?;
+        let opcode = Opcode::from_int(small).ok_or_else(invalid)?;
+
+        let mut modes = [ParameterMode::Position; 3];
+        for (position, mode) in modes.iter_mut().enumerate().take(opcode.parameter_count()) {
+            // Constant divisors are much cheaper than one that changes every iteration.
+            let digit = match position {
+                0 => small / 100,
+                1 => small / 1000,
+                _ => small / 10000,
+            } % 10;
+            *mode = match digit {
+                0 => ParameterMode::Position,
+                1 => ParameterMode::Immediate,
+                2 => ParameterMode::Relative,
+                mode => {
+                    return Err(IntcodeError::InvalidParameterMode {
+                        ip,
+                        opcode: word.clone(),
+                        mode,
+                    })
+                }
+            };
+        }
+
+        if opcode.writes() && modes[opcode.parameter_count() - 1] == ParameterMode::Immediate {
+            return Err(IntcodeError::ImmediateWrite {
+                ip,
+                opcode: word.clone(),
+            });
+        }
+        Ok(Header { opcode, modes })
+    }
+}
+
+impl<W: Word> Instruction<W> {
+    /// Decodes the instruction at `ip`, fetching words through `fetch`.
+    #[inline]
+    pub fn decode<F>(ip: usize, fetch: F) -> Result<Instruction<W>, IntcodeError<W>>
+    where
+        F: Fn(usize) -> W,
+    {
+        let word = fetch(ip);
+        let header = Header::decode(ip, &word)?;
+        Ok(Instruction::from_header(ip, word, header, fetch))
+    }
+
+    /// Completes an already decoded header with the parameter values following it.
+    #[inline]
+    pub(crate) fn from_header<F>(ip: usize, word: W, header: Header, fetch: F) -> Instruction<W>
+    where
+        F: Fn(usize) -> W,
+    {
+        let unused = || Parameter {
+            mode: ParameterMode::Position,
+            value: W::zero(),
+        };
+        let mut parameters = [unused(), unused(), unused()];
+        for (position, parameter) in parameters
+            .iter_mut()
+            .enumerate()
+            .take(header.opcode.parameter_count())
+        {
+            parameter.mode = header.modes[position];
+            parameter.value = fetch(ip + position + 1);
+        }
+        Instruction {
+            word,
+            opcode: header.opcode,
+            parameters,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn all_parameters(&self) -> &[Parameter<W>; 3] {
+        &self.parameters
+    }
+
+    pub fn parameters(&self) -> &[Parameter<W>] {
+        &self.parameters[..self.opcode.parameter_count()]
+    }
+
+    /// Number of words this instruction occupies, including the opcode itself.
+    pub fn size(&self) -> usize {
+        self.opcode.parameter_count() + 1
+    }
+}
+
+impl<W: Word> fmt::Display for Instruction<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.opcode.mnemonic())?;
+        let parameters = self.parameters();
+        let (reads, write) = if self.opcode.writes() {
+            let (last, reads) = parameters.split_last().unwrap();
+            (reads, Some(last))
+        } else {
+            (parameters, None)
+        };
+        for (i, parameter) in reads.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(f, "{}{}", separator, parameter)?;
+        }
+        if let Some(write) = write {
+            write!(f, " -> {}", write)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+//! Pluggable input and output for `Computer::execute_io`.
+//!
+//! Besides the types here, `VecDeque` and `Vec` work as a queue of inputs or a list of outputs,
+//! an `mpsc::Receiver` or `mpsc::Sender` connects a computer to another thread, and a `Computer`
+//! is itself an `OutputSink` that queues every value as its own input.
+
+use crate::{
+    computer::{Computer, ComputerState},
+    error::IntcodeError,
+    word::Word,
+};
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, Write},
+    sync::mpsc::{Receiver, Sender},
+};
+
+/// Supplies values to `IN` instructions.
+pub trait InputSource<W = i64> {
+    /// The next input value, or `None` if there is none right now. The computer then stops with
+    /// `ComputerState::InputRequired` and can be resumed once more input is available.
+    fn read_input(&mut self) -> Option<W>;
+}
+
+/// Receives the values of `OUT` instructions.
+pub trait OutputSink<W = i64> {
+    fn write_output(&mut self, value: W);
+}
+
+impl<W, S: InputSource<W> + ?Sized> InputSource<W> for &mut S {
+    fn read_input(&mut self) -> Option<W> {
+        (**self).read_input()
+    }
+}
+
+impl<W, S: OutputSink<W> + ?Sized> OutputSink<W> for &mut S {
+    fn write_output(&mut self, value: W) {
+        (**self).write_output(value);
+    }
+}
+
+/// Reads values front to back.
+impl<W> InputSource<W> for VecDeque<W> {
+    fn read_input(&mut self) -> Option<W> {
+        self.pop_front()
+    }
+}
+
+impl<W> OutputSink<W> for VecDeque<W> {
+    fn write_output(&mut self, value: W) {
+        self.push_back(value);
+    }
+}
+
+impl<W> OutputSink<W> for Vec<W> {
+    fn write_output(&mut self, value: W) {
+        self.push(value);
+    }
+}
+
+/// Blocks until the next value arrives, and reports no input once every sender is gone.
+impl<W> InputSource<W> for Receiver<W> {
+    fn read_input(&mut self) -> Option<W> {
+        self.recv().ok()
+    }
+}
+
+/// Values sent after the receiver is gone are dropped.
+impl<W> OutputSink<W> for Sender<W> {
+    fn write_output(&mut self, value: W) {
+        let _ = self.send(value);
+    }
+}
+
+/// Feeds every output into this computer's input queue.
+impl<W: Word> OutputSink<W> for Computer<W> {
+    fn write_output(&mut self, value: W) {
+        self.push_input(value);
+    }
+}
+
+/// Wraps a closure returning the next input.
+pub struct InputFn<F>(pub F);
+
+impl<W, F: FnMut() -> Option<W>> InputSource<W> for InputFn<F> {
+    fn read_input(&mut self) -> Option<W> {
+        (self.0)()
+    }
+}
+
+/// Wraps a closure called with every output.
+pub struct OutputFn<F>(pub F);
+
+impl<W, F: FnMut(W)> OutputSink<W> for OutputFn<F> {
+    fn write_output(&mut self, value: W) {
+        (self.0)(value);
+    }
+}
+
+/// Reads one value per line from `reader`, e.g. stdin or a file. Blank lines are skipped.
+///
+/// Reading stops at the end of the input or at the first line that fails to read or parse;
+/// `finish` reports that failure.
+pub struct ReadInput<R: BufRead> {
+    reader: R,
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> ReadInput<R> {
+    pub fn new(reader: R) -> ReadInput<R> {
+        ReadInput {
+            reader,
+            error: None,
+        }
+    }
+
+    /// Hands back the reader, reporting the first read or parse error if any.
+    pub fn finish(self) -> io::Result<R> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.reader),
+        }
+    }
+}
+
+impl<R: BufRead, W: Word> InputSource<W> for ReadInput<R> {
+    fn read_input(&mut self) -> Option<W> {
+        if self.error.is_some() {
+            return None;
+        }
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) if line.trim().is_empty() => {}
+                Ok(_) => break,
+                Err(err) => {
+                    self.error = Some(err);
+                    return None;
+                }
+            }
+        }
+        match line.trim().parse() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.error = Some(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid input value `{}`", line.trim()),
+                ));
+                None
+            }
+        }
+    }
+}
+
+/// Writes one value per line to `writer`, e.g. stdout or a file.
+pub struct WriteOutput<O: Write> {
+    writer: O,
+    error: Option<io::Error>,
+}
+
+impl<O: Write> WriteOutput<O> {
+    pub fn new(writer: O) -> WriteOutput<O> {
+        WriteOutput {
+            writer,
+            error: None,
+        }
+    }
+
+    /// Flushes the output and hands back the writer, reporting the first write error if any.
+    pub fn finish(mut self) -> io::Result<O> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<O: Write, W: Word> OutputSink<W> for WriteOutput<O> {
+    fn write_output(&mut self, value: W) {
+        if self.error.is_none() {
+            if let Err(err) = writeln!(self.writer, "{}", value) {
+                self.error = Some(err);
+            }
+        }
+    }
+}
+
+impl<W: Word> Computer<W> {
+    /// Runs until the program halts or needs input that neither the `inputs` queue nor `input`
+    /// can supply, passing every output to `output`. Returns `ComputerState::Halt` or
+    /// `ComputerState::InputRequired`.
+    pub fn execute_io<I, O>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+    ) -> Result<ComputerState<W>, IntcodeError<W>>
+    where
+        I: InputSource<W>,
+        O: OutputSink<W>,
+    {
+        loop {
+            match self.execute()? {
+                ComputerState::Output(value) => output.write_output(value),
+                ComputerState::InputRequired => match input.read_input() {
+                    Some(value) => self.push_input(value),
+                    None => return Ok(ComputerState::InputRequired),
+                },
+                state => return Ok(state),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble::assemble;
+    use std::sync::mpsc;
+
+    // Outputs twice every input, forever.
+    fn doubler() -> Computer {
+        let program = assemble(
+            "loop:   IN -> [x]
+                     MUL [x], #2 -> [x]
+                     OUT [x]
+                     JNZ #1, #loop
+             x:      DATA 0",
+        )
+        .unwrap();
+        Computer::new(program)
+    }
+
+    #[test]
+    fn wires_closures() {
+        let mut computer = doubler();
+        let mut next = 0;
+        let mut outputs = Vec::new();
+        let state = computer.execute_io(
+            &mut InputFn(|| {
+                next += 1;
+                if next <= 3 {
+                    Some(next)
+                } else {
+                    None
+                }
+            }),
+            &mut OutputFn(|value| outputs.push(value)),
+        );
+        assert_eq!(state, Ok(ComputerState::InputRequired));
+        assert_eq!(outputs, vec![2, 4, 6]);
+    }
+
+    #[test]
+    fn reads_the_queue_before_the_source() {
+        let mut computer = doubler();
+        computer.push_input(10);
+        let mut input: VecDeque<i64> = vec![20, 30].into();
+        let mut output = Vec::new();
+        assert_eq!(
+            computer.execute_io(&mut input, &mut output),
+            Ok(ComputerState::InputRequired)
+        );
+        assert_eq!(output, vec![20, 40, 60]);
+        assert!(input.is_empty());
+
+        // A halted program leaves the source alone.
+        let mut computer = Computer::new(vec![104, 5, 99]);
+        let mut input: VecDeque<i64> = vec![1].into();
+        let mut output = VecDeque::new();
+        assert_eq!(
+            computer.execute_io(&mut input, &mut output),
+            Ok(ComputerState::Halt)
+        );
+        assert_eq!(output, vec![5]);
+        assert_eq!(input, vec![1]);
+    }
+
+    #[test]
+    fn chains_computers() {
+        let mut first = doubler();
+        let mut second = doubler();
+        let mut input: VecDeque<i64> = vec![1, 2].into();
+        first.execute_io(&mut input, &mut second).unwrap();
+        let mut output = Vec::new();
+        second
+            .execute_io(&mut VecDeque::new(), &mut output)
+            .unwrap();
+        assert_eq!(output, vec![4, 8]);
+    }
+
+    #[test]
+    fn wires_channels() {
+        let (input, mut receiver) = mpsc::channel();
+        let (mut sender, output) = mpsc::channel();
+        input.send(3).unwrap();
+        input.send(4).unwrap();
+        drop(input);
+        assert_eq!(
+            doubler().execute_io(&mut receiver, &mut sender),
+            Ok(ComputerState::InputRequired)
+        );
+        drop(sender);
+        assert_eq!(output.iter().collect::<Vec<i64>>(), vec![6, 8]);
+    }
+
+    #[test]
+    fn reads_and_writes_lines() {
+        let mut input = ReadInput::new("1\n\n 2 \n".as_bytes());
+        let mut output = WriteOutput::new(Vec::new());
+        doubler().execute_io(&mut input, &mut output).unwrap();
+        assert!(input.finish().is_ok());
+        assert_eq!(output.finish().unwrap(), b"2\n4\n");
+
+        let mut input = ReadInput::new("1\nx\n3\n".as_bytes());
+        let mut output = WriteOutput::new(Vec::new());
+        doubler().execute_io(&mut input, &mut output).unwrap();
+        assert_eq!(
+            input.finish().unwrap_err().to_string(),
+            "invalid input value `x`"
+        );
+        assert_eq!(output.finish().unwrap(), b"2\n");
+    }
+}
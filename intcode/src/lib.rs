@@ -0,0 +1,54 @@
+//! A shared Intcode virtual machine used by every day that runs an Intcode program.
+
+mod ascii;
+mod assemble;
+mod budget;
+mod cfg;
+pub mod cli;
+mod code_write;
+mod computer;
+mod coverage;
+mod debugger;
+mod decode_cache;
+mod decompile;
+mod disassemble;
+mod error;
+mod instruction;
+mod io;
+mod memory;
+mod native;
+mod observer;
+mod outputs;
+mod profile;
+mod record;
+mod runtime;
+mod snapshot;
+mod trace;
+mod translate;
+mod undo;
+mod word;
+
+pub use ascii::{AsciiComputer, AsciiError, AsciiOutput};
+pub use assemble::{assemble, AssembleError, AssembleErrorKind};
+pub use cfg::{control_flow_graph, BasicBlock, ControlFlowGraph, Edge, EdgeKind};
+pub use code_write::{CodeWrite, CodeWriteDetector};
+pub use computer::{parse_program, parse_words, Computer, ComputerState, Program, StepResult};
+pub use coverage::{BranchCount, Coverage};
+pub use debugger::{Debugger, StopReason};
+pub use decompile::{decompile, Decompilation, Function};
+pub use disassemble::{disassemble, label_name, Disassembly, Entry};
+pub use error::IntcodeError;
+pub use instruction::{Instruction, Opcode, Parameter, ParameterMode};
+pub use io::{InputFn, InputSource, OutputFn, OutputSink, ReadInput, WriteOutput};
+pub use memory::Memory;
+pub use native::{NativeCode, NativeComputer, Translation};
+pub use observer::{MemoryWrite, StepEvent, StepObserver};
+pub use outputs::{OutputChunks, OutputError};
+pub use profile::Profiler;
+pub use record::{IoEvent, Recording, RecordingError, ReplayError};
+pub use runtime::{Finished, Runtime, RuntimeError};
+pub use snapshot::SnapshotError;
+pub use trace::{first_divergence, Divergence, Tracer};
+pub use translate::translate;
+pub use undo::UndoLog;
+pub use word::Word;
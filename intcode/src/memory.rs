@@ -0,0 +1,229 @@
+use crate::word::Word;
+use std::{array, collections::HashMap, sync::Arc};
+
+// Addresses below this limit live in pages that are added on demand, anything above it is kept
+// in a map so a single write to a huge address doesn't allocate a huge page table.
+const DENSE_LIMIT: usize = 1 << 24;
+
+// Number of words in a page.
+const PAGE_SIZE: usize = 128;
+
+type Page<W> = [W; PAGE_SIZE];
+
+/// Intcode memory that grows on demand.
+///
+/// Every address reads as zero until it is first written. The flat part is split into pages
+/// that are only added when a write lands past its end, so programs can touch arbitrarily high
+/// addresses.
+///
+/// Cloning only copies the page table, one pointer per page: clones share every page until one
+/// of them writes to it, at which point just that page is copied. A brute-force search that
+/// clones a computer for every move only pays for the few pages each move changes.
+#[derive(Debug, Clone)]
+pub struct Memory<W = i64> {
+    pages: Vec<Arc<Page<W>>>,
+    sparse: Arc<HashMap<usize, W>>,
+}
+
+impl<W: Word> Memory<W> {
+    pub fn new() -> Memory<W> {
+        Memory {
+            pages: Vec::new(),
+            sparse: Arc::new(HashMap::new()),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, address: usize) -> W {
+        match self.pages.get(address / PAGE_SIZE) {
+            Some(page) => page[address % PAGE_SIZE].clone(),
+            None => self.get_sparse(address),
+        }
+    }
+
+    #[inline]
+    pub fn set(&mut self, address: usize, value: W) {
+        let index = address / PAGE_SIZE;
+        if index < self.pages.len() {
+            Arc::make_mut(&mut self.pages[index])[address % PAGE_SIZE] = value;
+        } else {
+            self.set_slow(address, value);
+        }
+    }
+
+    /// Number of words backing the flat part of memory.
+    pub fn dense_len(&self) -> usize {
+        self.pages.len() * PAGE_SIZE
+    }
+
+    /// Number of words stored individually above the flat part.
+    pub fn sparse_len(&self) -> usize {
+        self.sparse.len()
+    }
+
+    /// Number of pages of the flat part that are shared, with clones of this memory or as a page
+    /// of zeros that was never written.
+    pub fn shared_pages(&self) -> usize {
+        self.pages
+            .iter()
+            .filter(|page| Arc::strong_count(page) > 1)
+            .count()
+    }
+
+    /// The flat part of memory, starting at address zero.
+    pub(crate) fn dense(&self) -> impl Iterator<Item = &W> + '_ {
+        self.pages.iter().flat_map(|page| page.iter())
+    }
+
+    /// Every non-zero word as `(address, value)`, in address order.
+    pub fn nonzero_words(&self) -> impl Iterator<Item = (usize, &W)> + '_ {
+        let mut sparse: Vec<(usize, &W)> = self
+            .sparse
+            .iter()
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(address, value)| (*address, value))
+            .collect();
+        sparse.sort_unstable_by_key(|(address, _)| *address);
+        self.dense()
+            .enumerate()
+            .filter(|(_, value)| !value.is_zero())
+            .chain(sparse)
+    }
+
+    #[cold]
+    fn get_sparse(&self, address: usize) -> W {
+        self.sparse.get(&address).cloned().unwrap_or_else(W::zero)
+    }
+
+    #[cold]
+    fn set_slow(&mut self, address: usize, value: W) {
+        if address < DENSE_LIMIT {
+            // New pages all share one page of zeros until they are written.
+            let zero = Arc::new(zero_page());
+            self.pages.resize(address / PAGE_SIZE + 1, zero);
+            self.set(address, value);
+        } else {
+            Arc::make_mut(&mut self.sparse).insert(address, value);
+        }
+    }
+}
+
+fn zero_page<W: Word>() -> Page<W> {
+    array::from_fn(|_| W::zero())
+}
+
+impl<W: Word> Default for Memory<W> {
+    fn default() -> Memory<W> {
+        Memory::new()
+    }
+}
+
+impl<W: Word> From<Vec<W>> for Memory<W> {
+    fn from(program: Vec<W>) -> Memory<W> {
+        let mut pages = Vec::with_capacity(program.len().div_ceil(PAGE_SIZE));
+        let mut words = program.into_iter();
+        while words.len() > 0 {
+            let mut page = zero_page();
+            for (slot, word) in page.iter_mut().zip(&mut words) {
+                *slot = word;
+            }
+            pages.push(Arc::new(page));
+        }
+        Memory {
+            pages,
+            sparse: Arc::new(HashMap::new()),
+        }
+    }
+}
+
+/// Two memories are equal when every address holds the same word, no matter how far either has
+/// grown.
+impl<W: Word> PartialEq for Memory<W> {
+    fn eq(&self, other: &Memory<W>) -> bool {
+        let common = self.pages.len().min(other.pages.len());
+        let zero = |page: &Arc<Page<W>>| page.iter().all(Word::is_zero);
+        self.pages
+            .iter()
+            .zip(other.pages.iter())
+            .all(|(a, b)| Arc::ptr_eq(a, b) || a[..] == b[..])
+            && self.pages[common..].iter().all(zero)
+            && other.pages[common..].iter().all(zero)
+            && self
+                .sparse
+                .iter()
+                .all(|(address, value)| other.get(*address) == *value)
+            && other
+                .sparse
+                .iter()
+                .all(|(address, value)| self.get(*address) == *value)
+    }
+}
+
+impl<W: Word> Eq for Memory<W> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_when_written_past_the_end() {
+        let mut memory = Memory::from(vec![1i64, 2, 3]);
+        assert_eq!(memory.dense_len(), PAGE_SIZE);
+        assert_eq!(memory.get(1_000), 0);
+        assert_eq!(memory.dense_len(), PAGE_SIZE);
+
+        memory.set(1_000, 7);
+        assert_eq!(memory.get(1_000), 7);
+        assert_eq!(memory.get(999), 0);
+        assert_eq!(memory.get(2), 3);
+        assert_eq!(memory.dense_len(), (1_000 / PAGE_SIZE + 1) * PAGE_SIZE);
+        assert_eq!(memory.sparse_len(), 0);
+    }
+
+    #[test]
+    fn keeps_high_addresses_sparse() {
+        let mut memory = Memory::from(vec![1i64, 2, 3]);
+        let high = [DENSE_LIMIT, 1 << 40, usize::MAX];
+        for (value, &address) in high.iter().enumerate() {
+            memory.set(address, value as i64 + 10);
+        }
+        for (value, &address) in high.iter().enumerate() {
+            assert_eq!(memory.get(address), value as i64 + 10);
+        }
+        assert_eq!(memory.get((1 << 40) + 1), 0);
+        assert_eq!(memory.dense_len(), PAGE_SIZE);
+        assert_eq!(memory.sparse_len(), high.len());
+        assert_eq!(
+            memory.nonzero_words().collect::<Vec<_>>(),
+            vec![
+                (0, &1),
+                (1, &2),
+                (2, &3),
+                (DENSE_LIMIT, &10),
+                (1 << 40, &11),
+                (usize::MAX, &12)
+            ]
+        );
+    }
+
+    #[test]
+    fn equality_ignores_growth() {
+        let mut grown = Memory::from(vec![1i64, 2, 3]);
+        grown.set(5_000, 0);
+        grown.set(1 << 30, 0);
+        assert_eq!(grown, Memory::from(vec![1, 2, 3]));
+        grown.set(1 << 30, 4);
+        assert_ne!(grown, Memory::from(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn clones_share_pages_until_written() {
+        let memory = Memory::from((0..1_000).collect::<Vec<i64>>());
+        let mut clone = memory.clone();
+        assert_eq!(clone.shared_pages(), memory.dense_len() / PAGE_SIZE);
+        clone.set(3, -1);
+        assert_eq!(clone.shared_pages(), memory.dense_len() / PAGE_SIZE - 1);
+        assert_eq!(memory.get(3), 3);
+        assert_eq!(clone.get(3), -1);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{
+    computer::{Computer, ComputerState, StepResult},
+    error::IntcodeError,
+    memory::Memory,
+    observer::{StepEvent, StepObserver},
+};
+use std::{convert::TryFrom, fmt};
+
+/// Runs translated code from `ip` with relative base `relative_base` until the computer halts,
+/// outputs or needs input, like `Computer::execute`.
+pub type NativeCode = fn(&mut NativeComputer, usize, i64) -> Result<ComputerState, IntcodeError>;
+
+/// A program translated to Rust by `translate`, as defined by the generated module.
+pub struct Translation {
+    pub program: &'static [i64],
+    /// One bit per word of the program that is part of a translated instruction.
+    pub code: &'static [u64],
+    pub run: NativeCode,
+}
+
+impl fmt::Debug for Translation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Translation")
+            .field("words", &self.program.len())
+            .finish()
+    }
+}
+
+/// A computer running a program translated ahead of time, with the same protocol as `Computer`.
+///
+/// The translated code only knows the program as it was translated. Code it doesn't know, like
+/// the targets of a jump table, is interpreted one instruction at a time until execution is back
+/// in translated code. As soon as the program writes to one of its translated instructions, the
+/// computer hands over to the interpreter at that instruction and keeps interpreting from then
+/// on. The same happens when `set_memory` changes an instruction, so patching code before
+/// running it works, just without the speedup. To keep the speedup, translate the patched
+/// program instead. Errors are always reported by the interpreter, so they are identical to
+/// those of a `Computer`.
+#[derive(Debug, Clone)]
+pub struct NativeComputer {
+    computer: Computer,
+    translation: &'static Translation,
+    interpreted: bool,
+}
+
+impl NativeComputer {
+    pub fn new(translation: &'static Translation) -> NativeComputer {
+        NativeComputer {
+            computer: Computer::new(translation.program.to_vec()),
+            translation,
+            interpreted: false,
+        }
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.computer.push_input(value);
+    }
+
+    pub fn execute(&mut self) -> Result<ComputerState, IntcodeError> {
+        if self.interpreted {
+            return self.computer.execute();
+        }
+        let (ip, relative_base) = (self.computer.ip(), self.computer.relative_base());
+        (self.translation.run)(self, ip, relative_base)
+    }
+
+    pub fn memory(&self) -> &Memory {
+        self.computer.memory()
+    }
+
+    /// Changes a word of memory. Changing a translated instruction switches to the interpreter.
+    pub fn set_memory(&mut self, address: usize, value: i64) {
+        if self.is_code(address) && self.get(address) != value {
+            self.interpreted = true;
+        }
+        self.computer.set_memory(address, value);
+    }
+
+    pub fn ip(&self) -> usize {
+        self.computer.ip()
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.computer.relative_base()
+    }
+
+    /// Whether the computer has handed over to the interpreter for good.
+    pub fn is_interpreted(&self) -> bool {
+        self.interpreted
+    }
+
+    /// The interpreter's view of this computer, to carry on with it directly.
+    pub fn into_computer(self) -> Computer {
+        self.computer
+    }
+
+    // The rest is only meant for translated code.
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn get(&self, address: usize) -> i64 {
+        self.computer.memory().get(address)
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn set(&mut self, address: usize, value: i64) {
+        self.computer.set_memory(address, value);
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn is_code(&self, address: usize) -> bool {
+        self.translation
+            .code
+            .get(address / 64)
+            .is_some_and(|bits| bits >> (address % 64) & 1 != 0)
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn address(&self, value: i64) -> Option<usize> {
+        usize::try_from(value).ok()
+    }
+
+    /// The address `offset` from `relative_base`, if there is one.
+    #[doc(hidden)]
+    #[inline]
+    pub fn relative(&self, relative_base: i64, offset: i64) -> Option<usize> {
+        relative_base
+            .checked_add(offset)
+            .and_then(|address| self.address(address))
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn input(&mut self) -> Option<i64> {
+        self.computer.inputs.pop_back()
+    }
+
+    /// Stores the position translated code stopped at and reports `state`.
+    #[doc(hidden)]
+    #[inline]
+    pub fn pause(
+        &mut self,
+        ip: usize,
+        relative_base: i64,
+        state: ComputerState,
+    ) -> Result<ComputerState, IntcodeError> {
+        self.computer.set_position(ip, relative_base);
+        Ok(state)
+    }
+
+    /// Hands over to the interpreter at the instruction at `ip`, which hasn't been executed yet.
+    #[doc(hidden)]
+    #[cold]
+    pub fn fall_back(
+        &mut self,
+        ip: usize,
+        relative_base: i64,
+    ) -> Result<ComputerState, IntcodeError> {
+        self.computer.set_position(ip, relative_base);
+        self.interpreted = true;
+        self.computer.execute()
+    }
+
+    /// Interprets the single instruction at `ip`, for code that wasn't translated. Returns the
+    /// state to report if the instruction stopped the computer, otherwise translated code can
+    /// carry on from `ip()`.
+    #[doc(hidden)]
+    pub fn interpret(
+        &mut self,
+        ip: usize,
+        relative_base: i64,
+    ) -> Option<Result<ComputerState, IntcodeError>> {
+        self.computer.set_position(ip, relative_base);
+        let mut write = LastWrite(None);
+        let state = match self.computer.step_with(&mut write) {
+            Ok(StepResult::Continue) => match write.0 {
+                Some(address) if self.is_code(address) => {
+                    self.interpreted = true;
+                    return Some(self.computer.execute());
+                }
+                _ => return None,
+            },
+            Ok(StepResult::Stop) => ComputerState::Halt,
+            Ok(StepResult::Output(output)) => ComputerState::Output(output),
+            Ok(StepResult::InputRequired) => ComputerState::InputRequired,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(Ok(state))
+    }
+}
+
+// Remembers where the last step wrote to.
+struct LastWrite(Option<usize>);
+
+impl StepObserver<i64> for LastWrite {
+    fn on_step(&mut self, event: &StepEvent<i64>) {
+        self.0 = event.write.as_ref().map(|write| write.address);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{instruction::Instruction, word::Word};
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryWrite<W = i64> {
+    pub address: usize,
+    pub old: W,
+    pub new: W,
+}
+
+/// Everything a single executed instruction did, as reported to a `StepObserver`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StepEvent<W = i64> {
+    pub ip: usize,
+    pub instruction: Instruction<W>,
+    operands: [W; 2],
+    operand_count: usize,
+    pub write: Option<MemoryWrite<W>>,
+    pub input: Option<W>,
+    pub output: Option<W>,
+    /// Relative base after the instruction executed.
+    pub relative_base: i64,
+    pub next_ip: usize,
+}
+
+impl<W: Word> StepEvent<W> {
+    pub(crate) fn new(ip: usize, instruction: Instruction<W>, relative_base: i64) -> StepEvent<W> {
+        StepEvent {
+            ip,
+            instruction,
+            operands: [W::zero(), W::zero()],
+            operand_count: 0,
+            write: None,
+            input: None,
+            output: None,
+            relative_base,
+            next_ip: ip,
+        }
+    }
+
+    pub(crate) fn push_operand(&mut self, value: W) {
+        if let Some(slot) = self.operands.get_mut(self.operand_count) {
+            *slot = value;
+            self.operand_count += 1;
+        }
+    }
+
+    /// Values of the parameters the instruction read, after resolving their modes. The target of
+    /// a jump that wasn't taken is never read and is left out.
+    pub fn operands(&self) -> &[W] {
+        &self.operands[..self.operand_count]
+    }
+}
+
+impl<W: Word> fmt::Display for StepEvent<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} |", self.ip, self.instruction)?;
+        for operand in self.operands() {
+            write!(f, " {}", operand)?;
+        }
+        if let Some(write) = &self.write {
+            write!(f, " | [{}] {} -> {}", write.address, write.old, write.new)?;
+        }
+        if let Some(input) = &self.input {
+            write!(f, " | in {}", input)?;
+        }
+        if let Some(output) = &self.output {
+            write!(f, " | out {}", output)?;
+        }
+        write!(f, " | rb {}", self.relative_base)
+    }
+}
+
+/// Hook called by `Computer::step_with` after every executed instruction.
+pub trait StepObserver<W = i64> {
+    fn on_step(&mut self, event: &StepEvent<W>);
+}
+
+impl<W> StepObserver<W> for () {
+    fn on_step(&mut self, _event: &StepEvent<W>) {}
+}
+
+impl<W, O: StepObserver<W> + ?Sized> StepObserver<W> for &mut O {
+    fn on_step(&mut self, event: &StepEvent<W>) {
+        (**self).on_step(event);
+    }
+}
+
+impl<W, A: StepObserver<W>, B: StepObserver<W>> StepObserver<W> for (A, B) {
+    fn on_step(&mut self, event: &StepEvent<W>) {
+        self.0.on_step(event);
+        self.1.on_step(event);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    computer::{Computer, ComputerState},
+    error::IntcodeError,
+    word::Word,
+};
+use std::{convert::TryInto, error::Error, fmt};
+
+/// Why the computer stopped before producing all the outputs that were asked for. The outputs it
+/// did produce are kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputError<W = i64> {
+    Intcode(IntcodeError<W>),
+    Halted { outputs: Vec<W> },
+    InputRequired { outputs: Vec<W> },
+}
+
+impl<W: Word> fmt::Display for OutputError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutputError::Intcode(err) => write!(f, "{}", err),
+            OutputError::Halted { outputs } => {
+                write!(f, "program halted after {} outputs", outputs.len())
+            }
+            OutputError::InputRequired { outputs } => {
+                write!(f, "program needs input after {} outputs", outputs.len())
+            }
+        }
+    }
+}
+
+impl<W: Word> Error for OutputError<W> {}
+
+impl<W> From<IntcodeError<W>> for OutputError<W> {
+    fn from(err: IntcodeError<W>) -> OutputError<W> {
+        OutputError::Intcode(err)
+    }
+}
+
+impl<W: Word> Computer<W> {
+    /// Runs until the program has output exactly `N` more values, for programs that output
+    /// fixed-size records like `[x, y, tile]`.
+    pub fn take_outputs<const N: usize>(&mut self) -> Result<[W; N], OutputError<W>> {
+        let mut outputs = Vec::with_capacity(N);
+        while outputs.len() < N {
+            match self.execute()? {
+                ComputerState::Output(output) => outputs.push(output),
+                state => return Err(stopped_early(state, outputs)),
+            }
+        }
+        Ok(outputs.try_into().ok().unwrap())
+    }
+
+    /// Runs until the program needs input and returns everything it output on the way.
+    pub fn run_until_input(&mut self) -> Result<Vec<W>, OutputError<W>> {
+        let mut outputs = Vec::new();
+        loop {
+            match self.execute()? {
+                ComputerState::Output(output) => outputs.push(output),
+                ComputerState::InputRequired => return Ok(outputs),
+                state => return Err(stopped_early(state, outputs)),
+            }
+        }
+    }
+
+    /// Iterates over the program's outputs in records of `N` values, until it halts or needs
+    /// input between two records. Stopping in the middle of a record is reported as an error,
+    /// which ends the iteration. Panics if `N` is zero, as empty records would never run out.
+    pub fn output_chunks<const N: usize>(&mut self) -> OutputChunks<'_, W, N> {
+        assert!(N > 0, "Output chunks must hold at least one value");
+        OutputChunks {
+            computer: self,
+            stopped: None,
+            done: false,
+        }
+    }
+}
+
+fn stopped_early<W: Word>(state: ComputerState<W>, outputs: Vec<W>) -> OutputError<W> {
+    match state {
+        ComputerState::Halt => OutputError::Halted { outputs },
+        ComputerState::InputRequired => OutputError::InputRequired { outputs },
+        state => unreachable!("`execute` stopped with {:?}", state),
+    }
+}
+
+/// Iterator returned by `Computer::output_chunks`.
+#[derive(Debug)]
+pub struct OutputChunks<'a, W, const N: usize> {
+    computer: &'a mut Computer<W>,
+    stopped: Option<ComputerState<W>>,
+    done: bool,
+}
+
+impl<W: Word, const N: usize> OutputChunks<'_, W, N> {
+    /// Why the iteration ended: `Halt` or `InputRequired` if the program stopped between two
+    /// records, `None` while it's still going or after an error.
+    pub fn stopped(&self) -> Option<&ComputerState<W>> {
+        self.stopped.as_ref()
+    }
+}
+
+impl<W: Word, const N: usize> Iterator for OutputChunks<'_, W, N> {
+    type Item = Result<[W; N], OutputError<W>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let chunk = self.computer.take_outputs();
+        self.done = chunk.is_err();
+        match chunk {
+            Ok(chunk) => Some(Ok(chunk)),
+            Err(OutputError::Halted { ref outputs }) if outputs.is_empty() => {
+                self.stopped = Some(ComputerState::Halt);
+                None
+            }
+            Err(OutputError::InputRequired { ref outputs }) if outputs.is_empty() => {
+                self.stopped = Some(ComputerState::InputRequired);
+                None
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
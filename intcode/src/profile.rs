@@ -0,0 +1,356 @@
+use crate::{
+    disassemble::label_name,
+    instruction::{Opcode, ParameterMode},
+    observer::{StepEvent, StepObserver},
+    word::Word,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+/// Counts executed instructions per opcode, per parameter mode and per address.
+///
+/// It also attributes every step to a basic block inside a call stack, for flamegraph tools that
+/// read folded stacks. Intcode has no call instruction, so calls are recognized by the usual
+/// convention of compiled programs: a function moves the relative base up by its frame size when
+/// it is entered and back down before it returns. The block that moves it up names the frame.
+/// A basic block starts at the first instruction executed and after every jump or halt.
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    steps: u64,
+    opcodes: [u64; 10],
+    modes: [[u64; 3]; 10],
+    // Steps per executed address, with the opcode last executed there.
+    addresses: BTreeMap<usize, (u64, Opcode)>,
+    frames: Vec<usize>,
+    stack_ids: HashMap<Vec<usize>, usize>,
+    stacks: Vec<Vec<usize>>,
+    stack: usize,
+    block: Option<usize>,
+    block_ended: bool,
+    // Steps in the current block not yet added to `block_steps`.
+    pending: u64,
+    block_steps: HashMap<(usize, usize), u64>,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        let mut stack_ids = HashMap::new();
+        stack_ids.insert(Vec::new(), 0);
+        Profiler {
+            steps: 0,
+            opcodes: [0; 10],
+            modes: [[0; 3]; 10],
+            addresses: BTreeMap::new(),
+            frames: Vec::new(),
+            stack_ids,
+            stacks: vec![Vec::new()],
+            stack: 0,
+            block: None,
+            block_ended: false,
+            pending: 0,
+            block_steps: HashMap::new(),
+        }
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn opcode_count(&self, opcode: Opcode) -> u64 {
+        self.opcodes[opcode as usize]
+    }
+
+    /// How many parameters of executed `opcode` instructions used `mode`.
+    pub fn mode_count(&self, opcode: Opcode, mode: ParameterMode) -> u64 {
+        self.modes[opcode as usize][mode as usize]
+    }
+
+    pub fn address_count(&self, address: usize) -> u64 {
+        self.addresses.get(&address).map_or(0, |(count, _)| *count)
+    }
+
+    /// Every executed address with its step count, most executed first.
+    pub fn hotspots(&self) -> Vec<(usize, u64)> {
+        let mut hotspots: Vec<(usize, u64)> = self
+            .addresses
+            .iter()
+            .map(|(address, (count, _))| (*address, *count))
+            .collect();
+        hotspots.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        hotspots
+    }
+
+    /// Every basic block, named by its first address, with its step count, most executed first.
+    pub fn block_hotspots(&self) -> Vec<(usize, u64)> {
+        let mut blocks = HashMap::new();
+        for ((_, block), count) in self.block_counts() {
+            *blocks.entry(block).or_insert(0) += count;
+        }
+        let mut blocks: Vec<(usize, u64)> = blocks.into_iter().collect();
+        blocks.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        blocks
+    }
+
+    /// Writes a human readable summary: steps per opcode with the parameter modes they used,
+    /// followed by the `top` most executed addresses and basic blocks.
+    pub fn write_report<O: Write>(&self, mut writer: O, top: usize) -> io::Result<()> {
+        let percent = |count: u64| 100.0 * count as f64 / self.steps.max(1) as f64;
+        writeln!(writer, "{} steps", self.steps)?;
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "{:<6} {:>12} {:>7} {:>12} {:>12} {:>12}",
+            "opcode", "steps", "%", "position", "immediate", "relative"
+        )?;
+        for &opcode in Opcode::ALL.iter() {
+            let count = self.opcode_count(opcode);
+            if count == 0 {
+                continue;
+            }
+            writeln!(
+                writer,
+                "{:<6} {:>12} {:>6.2}% {:>12} {:>12} {:>12}",
+                opcode.mnemonic(),
+                count,
+                percent(count),
+                self.mode_count(opcode, ParameterMode::Position),
+                self.mode_count(opcode, ParameterMode::Immediate),
+                self.mode_count(opcode, ParameterMode::Relative),
+            )?;
+        }
+
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "{:<8} {:>12} {:>7}  opcode",
+            "address", "steps", "%"
+        )?;
+        for (address, count) in self.hotspots().into_iter().take(top) {
+            let opcode = self.addresses[&address].1.mnemonic();
+            writeln!(
+                writer,
+                "{:<8} {:>12} {:>6.2}%  {}",
+                label_name(address),
+                count,
+                percent(count),
+                opcode
+            )?;
+        }
+
+        writeln!(writer)?;
+        writeln!(writer, "{:<8} {:>12} {:>7}", "block", "steps", "%")?;
+        for (block, count) in self.block_hotspots().into_iter().take(top) {
+            writeln!(
+                writer,
+                "{:<8} {:>12} {:>6.2}%",
+                label_name(block),
+                count,
+                percent(count)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes one `frame;frame;block steps` line per call stack and basic block, the folded
+    /// stacks format read by flamegraph tools. Frames and blocks are named like the labels of
+    /// `disassemble`.
+    pub fn write_folded<O: Write>(&self, mut writer: O) -> io::Result<()> {
+        let mut lines = BTreeMap::new();
+        for ((stack, block), count) in self.block_counts() {
+            let frames = &self.stacks[stack];
+            let mut names: Vec<String> = frames.iter().map(|&frame| label_name(frame)).collect();
+            if frames.last() != Some(&block) {
+                names.push(label_name(block));
+            }
+            *lines.entry(names.join(";")).or_insert(0) += count;
+        }
+        for (stack, count) in lines {
+            writeln!(writer, "{} {}", stack, count)?;
+        }
+        Ok(())
+    }
+
+    fn block_counts(&self) -> HashMap<(usize, usize), u64> {
+        let mut counts = self.block_steps.clone();
+        if let (Some(block), true) = (self.block, self.pending > 0) {
+            *counts.entry((self.stack, block)).or_insert(0) += self.pending;
+        }
+        counts
+    }
+
+    fn flush_block(&mut self) {
+        if let (Some(block), true) = (self.block, self.pending > 0) {
+            *self.block_steps.entry((self.stack, block)).or_insert(0) += self.pending;
+        }
+        self.pending = 0;
+    }
+
+    fn intern_stack(&mut self) -> usize {
+        if let Some(&id) = self.stack_ids.get(&self.frames) {
+            return id;
+        }
+        self.stacks.push(self.frames.clone());
+        self.stack_ids
+            .insert(self.frames.clone(), self.stacks.len() - 1);
+        self.stacks.len() - 1
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Profiler {
+        Profiler::new()
+    }
+}
+
+impl<W: Word> StepObserver<W> for Profiler {
+    fn on_step(&mut self, event: &StepEvent<W>) {
+        let instruction = &event.instruction;
+        let opcode = instruction.opcode;
+        self.steps += 1;
+        self.opcodes[opcode as usize] += 1;
+        for parameter in instruction.parameters() {
+            self.modes[opcode as usize][parameter.mode as usize] += 1;
+        }
+        let address = self.addresses.entry(event.ip).or_insert((0, opcode));
+        address.0 += 1;
+        address.1 = opcode;
+
+        if self.block_ended || self.block.is_none() {
+            self.flush_block();
+            self.block = Some(event.ip);
+            self.block_ended = false;
+        }
+        self.pending += 1;
+
+        match opcode {
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse | Opcode::Halt => self.block_ended = true,
+            Opcode::AdjustRelativeBase => {
+                let offset = &event.operands()[0];
+                if offset.is_zero() {
+                    return;
+                }
+                self.flush_block();
+                if *offset > W::zero() {
+                    self.frames.push(self.block.unwrap_or(event.ip));
+                } else {
+                    self.frames.pop();
+                }
+                self.stack = self.intern_stack();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assemble::assemble,
+        computer::{Computer, ComputerState},
+    };
+
+    // Calls `outer` twice, which sets up a frame, outputs and calls `inner` with a frame of its
+    // own.
+    const CALLS: &str = "
+            ARB #stack
+            ADD #first, #0 -> [r]
+            JNZ #1, #outer
+    first:  ADD #second, #0 -> [r]
+            JNZ #1, #outer
+    second: HLT
+    outer:  ARB #2
+            OUT #7
+            ADD #back, #0 -> [r]
+            JNZ #1, #inner
+    back:   ARB #-2
+            JNZ #1, [r]
+    inner:  ARB #3
+            ARB #-3
+            JNZ #1, [r]
+    stack:  DATA 0, 0, 0, 0, 0, 0";
+
+    fn profile(source: &str) -> Profiler {
+        let mut computer = Computer::new(assemble(source).unwrap());
+        let mut profiler = Profiler::new();
+        while computer.execute_with(&mut profiler).unwrap() != ComputerState::Halt {}
+        profiler
+    }
+
+    #[test]
+    fn counts_opcodes_modes_and_addresses() {
+        let profiler = profile(CALLS);
+        assert_eq!(profiler.steps(), 24);
+        assert_eq!(profiler.opcode_count(Opcode::AdjustRelativeBase), 9);
+        assert_eq!(profiler.opcode_count(Opcode::JumpIfTrue), 8);
+        assert_eq!(profiler.opcode_count(Opcode::Halt), 1);
+        assert_eq!(profiler.opcode_count(Opcode::Mul), 0);
+        assert_eq!(
+            profiler.mode_count(Opcode::JumpIfTrue, ParameterMode::Immediate),
+            12
+        );
+        assert_eq!(
+            profiler.mode_count(Opcode::JumpIfTrue, ParameterMode::Relative),
+            4
+        );
+        assert_eq!(profiler.mode_count(Opcode::Add, ParameterMode::Relative), 4);
+        assert_eq!(profiler.address_count(0), 1);
+        assert_eq!(profiler.address_count(17), 2);
+        assert_eq!(profiler.address_count(1), 0);
+        assert_eq!(profiler.address_count(1_000), 0);
+        assert_eq!(&profiler.hotspots()[..2], &[(17, 2), (19, 2)]);
+        assert_eq!(&profiler.block_hotspots()[..2], &[(17, 8), (33, 6)]);
+    }
+
+    #[test]
+    fn folds_steps_into_call_stacks() {
+        let mut folded = Vec::new();
+        profile(CALLS).write_folded(&mut folded).unwrap();
+        // The `ARB` leaving a frame still counts inside it, the jump back already in the caller.
+        assert_eq!(
+            String::from_utf8(folded).unwrap(),
+            "L0000 3
+L0000;L0009 2
+L0000;L0016 1
+L0000;L0017 8
+L0000;L0017;L0028 2
+L0000;L0017;L0033 6
+L0000;L0028 2
+"
+        );
+    }
+
+    #[test]
+    fn reports_the_top_entries() {
+        let mut report = Vec::new();
+        profile(CALLS).write_report(&mut report, 1).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.starts_with("24 steps\n"));
+        assert!(report
+            .contains("\nARB               9  37.50%            0            9            0\n"));
+        assert!(report.contains("\nL0017               2   8.33%  ARB\n"));
+        assert!(!report.contains("\nL0019 "));
+    }
+
+    #[test]
+    fn counts_instructions_at_high_addresses() {
+        // Writes a `HLT` at 2^40 and jumps to it.
+        let high = 1 << 40;
+        let program = vec![1101, 99, 0, high as i64, 1105, 1, high as i64];
+        let mut computer = Computer::new(program);
+        let mut profiler = Profiler::new();
+        assert_eq!(
+            computer.execute_with(&mut profiler),
+            Ok(ComputerState::Halt)
+        );
+        assert_eq!(profiler.address_count(high), 1);
+        assert_eq!(profiler.hotspots(), [(0, 1), (4, 1), (high, 1)]);
+        let mut report = Vec::new();
+        profiler.write_report(&mut report, 3).unwrap();
+        assert!(String::from_utf8(report)
+            .unwrap()
+            .contains("\nL1099511627776            1  33.33%  HLT\n"));
+    }
+}
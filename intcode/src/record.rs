@@ -0,0 +1,339 @@
+//! Recording the input and output of a run, and replaying it as a regression test.
+//!
+//! A recording is a line-oriented text file:
+//!
+//! ```text
+//! intcode-recording 1
+//! out 1,2,3
+//! in 0
+//! out 2,2,4
+//! halt
+//! ```
+//!
+//! Each `in` line holds consecutive values passed to `push_input`, each `out` line consecutive
+//! values the computer output, in the order they happened. A final `halt` means the program
+//! halted while recorded. Blank lines and lines starting with `#` are ignored.
+
+use crate::{
+    computer::{Computer, ComputerState},
+    error::IntcodeError,
+    observer::StepObserver,
+    word::Word,
+};
+use std::{
+    env,
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufWriter, Write},
+    sync::Arc,
+};
+
+const HEADER: &str = "intcode-recording 1";
+
+// Environment variable that makes the days record their interactive runs, naming the file to
+// save the recording to.
+const RECORD_VAR: &str = "INTCODE_RECORD";
+
+const VALUES_PER_LINE: usize = 64;
+
+/// Something that happened at a computer's input or output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IoEvent<W = i64> {
+    Input(W),
+    Output(W),
+    Halt,
+}
+
+/// Everything that went in and out of a computer while it was recording, see
+/// `Computer::start_recording`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording<W = i64> {
+    pub events: Vec<IoEvent<W>>,
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    /// The first line isn't a recording header this version understands.
+    InvalidHeader(String),
+    /// A malformed line, with its 1-based line number.
+    InvalidLine {
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordingError::Io(err) => write!(f, "{}", err),
+            RecordingError::InvalidHeader(header) => {
+                write!(f, "unsupported recording header `{}`", header)
+            }
+            RecordingError::InvalidLine { line, text } => {
+                write!(f, "line {}: invalid recording line `{}`", line, text)
+            }
+        }
+    }
+}
+
+impl Error for RecordingError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RecordingError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(err: io::Error) -> RecordingError {
+        RecordingError::Io(err)
+    }
+}
+
+/// Why a replay didn't go the way it was recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError<W = i64> {
+    Intcode(IntcodeError<W>),
+    /// The computer stopped with `actual` where the recording has `expected`, the event at
+    /// `index`.
+    Mismatch {
+        index: usize,
+        expected: IoEvent<W>,
+        actual: ComputerState<W>,
+    },
+}
+
+impl<W: Word> fmt::Display for ReplayError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Intcode(err) => write!(f, "{}", err),
+            ReplayError::Mismatch {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "event {}: expected {:?}, but the computer stopped with {:?}",
+                index, expected, actual
+            ),
+        }
+    }
+}
+
+impl<W: Word> Error for ReplayError<W> {}
+
+impl<W> From<IntcodeError<W>> for ReplayError<W> {
+    fn from(err: IntcodeError<W>) -> ReplayError<W> {
+        ReplayError::Intcode(err)
+    }
+}
+
+impl<W: Word> Recording<W> {
+    pub fn new() -> Recording<W> {
+        Recording { events: Vec::new() }
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = &W> + '_ {
+        self.events.iter().filter_map(|event| match event {
+            IoEvent::Input(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    pub fn outputs(&self) -> impl Iterator<Item = &W> + '_ {
+        self.events.iter().filter_map(|event| match event {
+            IoEvent::Output(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Runs `computer` again, feeding it the recorded inputs at the points they were pushed and
+    /// checking that it makes the recorded outputs and halts where the recording does. Returns
+    /// the computer as it is after the last recorded event.
+    ///
+    /// Any memory patches the recorded run made before it started have to be made to `computer`
+    /// as well.
+    pub fn replay(&self, computer: Computer<W>) -> Result<Computer<W>, ReplayError<W>> {
+        self.replay_with(computer, &mut ())
+    }
+
+    /// Like `replay`, but reports every executed instruction to `observer`.
+    pub fn replay_with<O: StepObserver<W>>(
+        &self,
+        mut computer: Computer<W>,
+        observer: &mut O,
+    ) -> Result<Computer<W>, ReplayError<W>> {
+        for (index, event) in self.events.iter().enumerate() {
+            if let IoEvent::Input(value) = event {
+                computer.push_input(value.clone());
+                continue;
+            }
+            let state = computer.execute_with(observer)?;
+            let matches = match (event, &state) {
+                (IoEvent::Output(expected), ComputerState::Output(actual)) => expected == actual,
+                (IoEvent::Halt, ComputerState::Halt) => true,
+                _ => false,
+            };
+            if !matches {
+                return Err(ReplayError::Mismatch {
+                    index,
+                    expected: event.clone(),
+                    actual: state,
+                });
+            }
+        }
+        Ok(computer)
+    }
+
+    /// Writes the recording in the format described in the module docs.
+    pub fn save<O: Write>(&self, mut writer: O) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        let mut events = self.events.iter().peekable();
+        while let Some(event) = events.next() {
+            let (kind, first) = match event {
+                IoEvent::Input(value) => ("in", value),
+                IoEvent::Output(value) => ("out", value),
+                IoEvent::Halt => {
+                    writeln!(writer, "halt")?;
+                    continue;
+                }
+            };
+            let mut values = vec![first.to_string()];
+            while values.len() < VALUES_PER_LINE {
+                match (event, events.peek()) {
+                    (IoEvent::Input(_), Some(IoEvent::Input(value)))
+                    | (IoEvent::Output(_), Some(IoEvent::Output(value))) => {
+                        values.push(value.to_string());
+                        events.next();
+                    }
+                    _ => break,
+                }
+            }
+            writeln!(writer, "{} {}", kind, values.join(","))?;
+        }
+        writer.flush()
+    }
+
+    /// Reads a recording written by `save`.
+    pub fn load<R: BufRead>(reader: R) -> Result<Recording<W>, RecordingError> {
+        let mut lines = reader.lines();
+        let header = lines.next().transpose()?.unwrap_or_default();
+        if header.trim() != HEADER {
+            return Err(RecordingError::InvalidHeader(header));
+        }
+
+        let mut events = Vec::new();
+        for (index, text) in lines.enumerate() {
+            let text = text?;
+            let invalid = || RecordingError::InvalidLine {
+                line: index + 2,
+                text: text.clone(),
+            };
+            let trimmed = text.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let mut fields = trimmed.split_whitespace();
+            let (event, values): (fn(W) -> IoEvent<W>, &str) =
+                match (fields.next(), fields.next(), fields.next()) {
+                    (Some("halt"), None, None) => {
+                        events.push(IoEvent::Halt);
+                        continue;
+                    }
+                    (Some("in"), Some(values), None) => (IoEvent::Input, values),
+                    (Some("out"), Some(values), None) => (IoEvent::Output, values),
+                    _ => return Err(invalid()),
+                };
+            for value in values.split(',') {
+                events.push(event(value.parse().map_err(|_| invalid())?));
+            }
+        }
+        Ok(Recording { events })
+    }
+}
+
+// What a recording computer has recorded so far. Cloning it shares the events recorded before,
+// so exploring with many clones of a recording computer doesn't copy the recording every time.
+#[derive(Debug)]
+pub(crate) struct Recorder<W> {
+    earlier: Option<Arc<Segment<W>>>,
+    events: Vec<IoEvent<W>>,
+    // Whether the last event was a halt, so executing a halted computer again doesn't add more.
+    halted: bool,
+}
+
+#[derive(Debug)]
+struct Segment<W> {
+    earlier: Option<Arc<Segment<W>>>,
+    events: Vec<IoEvent<W>>,
+}
+
+impl<W: Clone> Recorder<W> {
+    pub(crate) fn new() -> Recorder<W> {
+        Recorder {
+            earlier: None,
+            events: Vec::new(),
+            halted: false,
+        }
+    }
+
+    pub(crate) fn push(&mut self, event: IoEvent<W>) {
+        let halt = matches!(event, IoEvent::Halt);
+        if !(halt && self.halted) {
+            self.events.push(event);
+        }
+        self.halted = halt;
+    }
+
+    pub(crate) fn recording(&self) -> Recording<W> {
+        let mut segments = vec![&self.events];
+        let mut earlier = &self.earlier;
+        while let Some(segment) = earlier {
+            segments.push(&segment.events);
+            earlier = &segment.earlier;
+        }
+        Recording {
+            events: segments.into_iter().rev().flatten().cloned().collect(),
+        }
+    }
+}
+
+impl<W: Clone> Clone for Recorder<W> {
+    fn clone(&self) -> Recorder<W> {
+        let segment = Segment {
+            earlier: self.earlier.clone(),
+            events: self.events.clone(),
+        };
+        Recorder {
+            earlier: Some(Arc::new(segment)),
+            events: Vec::new(),
+            halted: self.halted,
+        }
+    }
+}
+
+impl<W> Drop for Segment<W> {
+    // Unlinks the chain one segment at a time, long chains would overflow the stack otherwise.
+    fn drop(&mut self) {
+        let mut earlier = self.earlier.take();
+        while let Some(segment) = earlier {
+            earlier = Arc::into_inner(segment).and_then(|mut segment| segment.earlier.take());
+        }
+    }
+}
+
+// Whether a recording was asked for through `RECORD_VAR`.
+pub(crate) fn requested() -> bool {
+    env::var_os(RECORD_VAR).is_some()
+}
+
+// Saves `recording` to the file named by `RECORD_VAR`, if it is set.
+pub(crate) fn save_requested<W: Word>(recording: &Recording<W>) -> io::Result<()> {
+    match env::var_os(RECORD_VAR) {
+        Some(path) => recording.save(BufWriter::new(File::create(path)?)),
+        None => Ok(()),
+    }
+}
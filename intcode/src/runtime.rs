@@ -0,0 +1,434 @@
+use crate::{
+    computer::{Computer, ComputerState},
+    error::IntcodeError,
+    word::Word,
+};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    sync::{Condvar, Mutex, MutexGuard},
+    thread,
+};
+
+const DEFAULT_CAPACITY: usize = 64;
+
+/// Runs a set of computers, each on its own thread, with outputs wired to inputs.
+///
+/// Every machine has a bounded input channel. A machine that runs out of input blocks until
+/// another one sends it a value, and a machine sending to a full channel blocks until there is
+/// room. Values sent to a machine that has already halted are dropped. The run ends when every
+/// machine has halted, or with a deadlock once every machine still running is blocked for good.
+#[derive(Debug, Clone)]
+pub struct Runtime<W = i64> {
+    machines: Vec<Machine<W>>,
+    capacity: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Machine<W> {
+    computer: Computer<W>,
+    targets: Vec<usize>,
+    collect: bool,
+}
+
+/// How a `Runtime` run ended without errors.
+#[derive(Debug, Clone)]
+pub struct Finished<W = i64> {
+    /// Whether the run was stopped because the machines still running could never continue.
+    pub deadlock: bool,
+    /// The machines in the order they were added. Values still waiting in a machine's channel
+    /// are moved to its `inputs`.
+    pub computers: Vec<Computer<W>>,
+    /// Everything each machine output, for the machines passed to `Runtime::collect`.
+    pub outputs: Vec<Vec<W>>,
+}
+
+/// A machine stopped with an error, which stops every other machine as well.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RuntimeError<W = i64> {
+    pub machine: usize,
+    pub error: IntcodeError<W>,
+}
+
+impl<W: Word> fmt::Display for RuntimeError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "machine {}: {}", self.machine, self.error)
+    }
+}
+
+impl<W: Word> Error for RuntimeError<W> {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Status {
+    Running,
+    Receiving,
+    Sending(usize),
+    Done,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Stop {
+    Deadlock,
+    Failed,
+}
+
+struct State<W> {
+    queues: Vec<VecDeque<W>>,
+    status: Vec<Status>,
+    stop: Option<Stop>,
+}
+
+impl<W> State<W> {
+    /// Whether no machine can ever make progress again even though some haven't halted.
+    fn deadlocked(&self, capacity: usize) -> bool {
+        let blocked = |machine: usize| match self.status[machine] {
+            Status::Running => false,
+            Status::Receiving => self.queues[machine].is_empty(),
+            Status::Sending(target) => {
+                self.queues[target].len() >= capacity && self.status[target] != Status::Done
+            }
+            Status::Done => true,
+        };
+        (0..self.status.len()).all(blocked)
+            && self.status.iter().any(|status| *status != Status::Done)
+    }
+}
+
+struct Channels<W> {
+    state: Mutex<State<W>>,
+    changed: Condvar,
+    capacity: usize,
+}
+
+impl<W> Channels<W> {
+    fn lock(&self) -> MutexGuard<'_, State<W>> {
+        self.state.lock().unwrap()
+    }
+
+    /// Marks `machine` as blocked and waits for a change, or stops everything if that leaves
+    /// every machine blocked. Returns `None` once the run is being stopped.
+    fn block<'a>(
+        &self,
+        mut state: MutexGuard<'a, State<W>>,
+        machine: usize,
+        status: Status,
+    ) -> Option<MutexGuard<'a, State<W>>> {
+        state.status[machine] = status;
+        if state.deadlocked(self.capacity) {
+            state.stop = Some(Stop::Deadlock);
+            self.changed.notify_all();
+            return None;
+        }
+        Some(self.changed.wait(state).unwrap())
+    }
+
+    fn receive(&self, machine: usize) -> Option<W> {
+        let mut state = self.lock();
+        loop {
+            if state.stop.is_some() {
+                return None;
+            }
+            if let Some(value) = state.queues[machine].pop_front() {
+                state.status[machine] = Status::Running;
+                self.changed.notify_all();
+                return Some(value);
+            }
+            state = self.block(state, machine, Status::Receiving)?;
+        }
+    }
+
+    fn send(&self, machine: usize, target: usize, value: W) -> bool {
+        let mut state = self.lock();
+        loop {
+            if state.stop.is_some() {
+                return false;
+            }
+            if state.status[target] == Status::Done {
+                state.status[machine] = Status::Running;
+                return true;
+            }
+            if state.queues[target].len() < self.capacity {
+                state.queues[target].push_back(value);
+                state.status[machine] = Status::Running;
+                self.changed.notify_all();
+                return true;
+            }
+            state = match self.block(state, machine, Status::Sending(target)) {
+                Some(state) => state,
+                None => return false,
+            };
+        }
+    }
+
+    fn finish(&self, machine: usize, failed: bool) {
+        let mut state = self.lock();
+        state.status[machine] = Status::Done;
+        if failed {
+            state.stop = Some(Stop::Failed);
+        } else if state.stop.is_none() && state.deadlocked(self.capacity) {
+            state.stop = Some(Stop::Deadlock);
+        }
+        self.changed.notify_all();
+    }
+}
+
+impl<W: Word + Send + Sync> Runtime<W> {
+    pub fn new() -> Runtime<W> {
+        Runtime::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Like `new`, with room for `capacity` values in every machine's input channel.
+    pub fn with_capacity(capacity: usize) -> Runtime<W> {
+        Runtime {
+            machines: Vec::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Adds a machine and returns its id. Input already queued on `computer` is read before
+    /// anything arriving through its channel.
+    pub fn add(&mut self, computer: Computer<W>) -> usize {
+        self.machines.push(Machine {
+            computer,
+            targets: Vec::new(),
+            collect: false,
+        });
+        self.machines.len() - 1
+    }
+
+    /// Sends every output of `from` to `to`. A machine connected to several others sends each
+    /// output to all of them, in the order they were connected.
+    pub fn connect(&mut self, from: usize, to: usize) {
+        assert!(to < self.machines.len(), "No machine with id {}", to);
+        self.machines[from].targets.push(to);
+    }
+
+    /// Queues `value` as input for `machine`, after any input already queued on it.
+    pub fn push_input(&mut self, machine: usize, value: W) {
+        self.machines[machine].computer.push_input(value);
+    }
+
+    /// Records every output of `machine` in `Finished::outputs`.
+    pub fn collect(&mut self, machine: usize) {
+        self.machines[machine].collect = true;
+    }
+
+    /// Starts every machine and waits for the run to end.
+    pub fn run(self) -> Result<Finished<W>, RuntimeError<W>> {
+        let count = self.machines.len();
+        let channels = Channels {
+            state: Mutex::new(State {
+                queues: (0..count).map(|_| VecDeque::new()).collect(),
+                status: vec![Status::Running; count],
+                stop: None,
+            }),
+            changed: Condvar::new(),
+            capacity: self.capacity,
+        };
+
+        let results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .machines
+                .into_iter()
+                .enumerate()
+                .map(|(id, machine)| {
+                    let channels = &channels;
+                    scope.spawn(move || run_machine(id, machine, channels))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        let mut state = channels.state.into_inner().unwrap();
+        let mut finished = Finished {
+            deadlock: state.stop == Some(Stop::Deadlock),
+            computers: Vec::with_capacity(count),
+            outputs: Vec::with_capacity(count),
+        };
+        let mut error = None;
+        for (id, (mut computer, outputs, result)) in results.into_iter().enumerate() {
+            for value in state.queues[id].drain(..) {
+                computer.push_input(value);
+            }
+            if let (Err(err), None) = (result, &error) {
+                error = Some(RuntimeError {
+                    machine: id,
+                    error: err,
+                });
+            }
+            finished.computers.push(computer);
+            finished.outputs.push(outputs);
+        }
+        match error {
+            Some(err) => Err(err),
+            None => Ok(finished),
+        }
+    }
+}
+
+impl<W: Word + Send + Sync> Default for Runtime<W> {
+    fn default() -> Runtime<W> {
+        Runtime::new()
+    }
+}
+
+fn run_machine<W: Word>(
+    id: usize,
+    machine: Machine<W>,
+    channels: &Channels<W>,
+) -> (Computer<W>, Vec<W>, Result<(), IntcodeError<W>>) {
+    let Machine {
+        mut computer,
+        targets,
+        collect,
+    } = machine;
+    let mut outputs = Vec::new();
+    let result = loop {
+        match computer.execute() {
+            Ok(ComputerState::Output(value)) => {
+                if collect {
+                    outputs.push(value.clone());
+                }
+                if !targets
+                    .iter()
+                    .all(|&target| channels.send(id, target, value.clone()))
+                {
+                    break Ok(());
+                }
+            }
+            Ok(ComputerState::InputRequired) => match channels.receive(id) {
+                Some(value) => computer.push_input(value),
+                None => break Ok(()),
+            },
+            Ok(_) => break Ok(()),
+            Err(err) => break Err(err),
+        }
+    };
+    channels.finish(id, result.is_err());
+    (computer, outputs, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble::assemble;
+    use std::{sync::mpsc, time::Duration};
+
+    fn computer(source: &str) -> Computer {
+        Computer::new(assemble(source).unwrap())
+    }
+
+    // Adds one to every input and passes it on, halting once it has sent 10 or more.
+    fn incrementer() -> Computer {
+        computer(
+            "loop:   IN -> [x]
+                     ADD [x], #1 -> [x]
+                     OUT [x]
+                     LT [x], #10 -> [more]
+                     JNZ [more], #loop
+                     HLT
+             x:      DATA 0
+             more:   DATA 0",
+        )
+    }
+
+    // Runs on another thread, so a run that never ends fails the test instead of hanging it.
+    fn run(runtime: Runtime) -> Result<Finished, RuntimeError> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || sender.send(runtime.run()));
+        receiver
+            .recv_timeout(Duration::from_secs(10))
+            .expect("the runtime didn't stop")
+    }
+
+    #[test]
+    fn runs_a_feedback_loop() {
+        let mut runtime = Runtime::new();
+        let a = runtime.add(incrementer());
+        let b = runtime.add(incrementer());
+        runtime.connect(a, b);
+        runtime.connect(b, a);
+        runtime.collect(a);
+        runtime.collect(b);
+        runtime.push_input(a, 0);
+
+        let finished = run(runtime).unwrap();
+        assert!(!finished.deadlock);
+        assert_eq!(finished.outputs[a], vec![1, 3, 5, 7, 9, 11]);
+        assert_eq!(finished.outputs[b], vec![2, 4, 6, 8, 10]);
+        // The last value went to a machine that had already halted.
+        assert!(finished.computers[b].inputs.is_empty());
+    }
+
+    #[test]
+    fn reports_machines_waiting_for_each_other() {
+        let mut runtime = Runtime::new();
+        let a = runtime.add(incrementer());
+        let b = runtime.add(incrementer());
+        runtime.connect(a, b);
+        runtime.connect(b, a);
+
+        let finished = run(runtime).unwrap();
+        assert!(finished.deadlock);
+        for computer in &finished.computers {
+            assert_eq!(computer.ip(), 0);
+        }
+    }
+
+    #[test]
+    fn reports_machines_blocked_on_full_channels() {
+        let sender = "loop: OUT #1\n JNZ #1, #loop";
+        let mut runtime = Runtime::with_capacity(2);
+        let a = runtime.add(computer(sender));
+        let b = runtime.add(computer(sender));
+        runtime.connect(a, b);
+        runtime.connect(b, a);
+
+        let finished = run(runtime).unwrap();
+        assert!(finished.deadlock);
+        // What was left in the channels is handed back as input.
+        assert_eq!(finished.computers[a].inputs.len(), 2);
+        assert_eq!(finished.computers[b].inputs.len(), 2);
+    }
+
+    #[test]
+    fn unblocks_senders_when_the_receiver_halts() {
+        let mut runtime = Runtime::with_capacity(1);
+        let a = runtime.add(computer("OUT #1\n OUT #2\n OUT #3\n HLT"));
+        let b = runtime.add(computer("IN -> [0]\n HLT"));
+        runtime.connect(a, b);
+        runtime.collect(a);
+
+        let finished = run(runtime).unwrap();
+        assert!(!finished.deadlock);
+        assert_eq!(finished.outputs[a], vec![1, 2, 3]);
+        assert_eq!(finished.computers[b].memory().get(0), 1);
+    }
+
+    #[test]
+    fn an_error_stops_blocked_machines() {
+        let mut runtime = Runtime::new();
+        // Waits for input nobody sends.
+        runtime.add(computer("IN -> [0]\n HLT"));
+        // Counts for a while, so the other machine is blocked by the time this one fails.
+        let failing = runtime.add(computer(
+            "loop:   ADD [n], #1 -> [n]
+                     LT [n], #1000 -> [more]
+                     JNZ [more], #loop
+                     DATA 42
+             n:      DATA 0
+             more:   DATA 0",
+        ));
+
+        let err = run(runtime).unwrap_err();
+        assert_eq!(err.machine, failing);
+        assert_eq!(
+            err.error,
+            IntcodeError::InvalidOpcode { ip: 11, opcode: 42 }
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Saving and restoring the complete state of a `Computer`.
+//!
+//! A snapshot is a line-oriented text file:
+//!
+//! ```text
+//! intcode-snapshot 1
+//! ip 42
+//! relative-base 1000
+//! inputs 7,-3
+//! memory 0 1,0,0,3,99
+//! memory 1000 12
+//! ```
+//!
+//! The first line names the format version. `ip` and `relative-base` are required. `inputs` lists
+//! the queued input values in the order the program will read them and may be left out when the
+//! queue is empty. Each `memory` line holds a start address followed by the consecutive words
+//! stored from there; addresses not covered by any `memory` line are zero. Blank lines and lines
+//! starting with `#` are ignored.
+
+use crate::{computer::Computer, memory::Memory, word::Word};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+const HEADER: &str = "intcode-snapshot 1";
+
+// Runs of zeros shorter than this are written inline instead of starting a new `memory` line.
+const MAX_ZERO_GAP: usize = 16;
+
+const WORDS_PER_LINE: usize = 64;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// The first line isn't a snapshot header this version understands.
+    InvalidHeader(String),
+    /// A malformed line, with its 1-based line number.
+    InvalidLine {
+        line: usize,
+        text: String,
+    },
+    MissingField(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "{}", err),
+            SnapshotError::InvalidHeader(header) => {
+                write!(f, "unsupported snapshot header `{}`", header)
+            }
+            SnapshotError::InvalidLine { line, text } => {
+                write!(f, "line {}: invalid snapshot line `{}`", line, text)
+            }
+            SnapshotError::MissingField(field) => write!(f, "snapshot is missing `{}`", field),
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnapshotError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> SnapshotError {
+        SnapshotError::Io(err)
+    }
+}
+
+impl<W: Word> Computer<W> {
+    /// Writes the complete machine state in the snapshot format described in the module docs.
+    pub fn save<O: Write>(&self, mut writer: O) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(writer, "ip {}", self.ip())?;
+        writeln!(writer, "relative-base {}", self.relative_base())?;
+        if !self.inputs.is_empty() {
+            // The queue is consumed from the back.
+            let inputs: Vec<String> = self.inputs.iter().rev().map(W::to_string).collect();
+            writeln!(writer, "inputs {}", inputs.join(","))?;
+        }
+        for (start, words) in memory_runs(self.memory()) {
+            for (index, chunk) in words.chunks(WORDS_PER_LINE).enumerate() {
+                let words: Vec<String> = chunk.iter().map(W::to_string).collect();
+                writeln!(
+                    writer,
+                    "memory {} {}",
+                    start + index * WORDS_PER_LINE,
+                    words.join(",")
+                )?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Restores a computer written by `save`.
+    pub fn load<R: BufRead>(reader: R) -> Result<Computer<W>, SnapshotError> {
+        let mut lines = reader.lines();
+        let header = lines.next().transpose()?.unwrap_or_default();
+        if header.trim() != HEADER {
+            return Err(SnapshotError::InvalidHeader(header));
+        }
+
+        let mut ip = None;
+        let mut relative_base = None;
+        let mut inputs = VecDeque::new();
+        let mut memory = Memory::new();
+        for (index, text) in lines.enumerate() {
+            let text = text?;
+            let invalid = || SnapshotError::InvalidLine {
+                line: index + 2,
+                text: text.clone(),
+            };
+            let trimmed = text.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let mut fields = trimmed.split_whitespace();
+            match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                (Some("ip"), Some(value), None, None) => {
+                    ip = Some(value.parse().map_err(|_| invalid())?)
+                }
+                (Some("relative-base"), Some(value), None, None) => {
+                    relative_base = Some(value.parse().map_err(|_| invalid())?)
+                }
+                (Some("inputs"), Some(values), None, None) => {
+                    for value in parse_words(values).ok_or_else(invalid)? {
+                        inputs.push_front(value);
+                    }
+                }
+                (Some("memory"), Some(start), Some(values), None) => {
+                    let start: usize = start.parse().map_err(|_| invalid())?;
+                    for (offset, value) in parse_words(values)
+                        .ok_or_else(invalid)?
+                        .into_iter()
+                        .enumerate()
+                    {
+                        let address = start.checked_add(offset).ok_or_else(invalid)?;
+                        memory.set(address, value);
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        let ip = ip.ok_or(SnapshotError::MissingField("ip"))?;
+        let relative_base = relative_base.ok_or(SnapshotError::MissingField("relative-base"))?;
+        Ok(Computer::from_parts(memory, ip, relative_base, inputs))
+    }
+}
+
+fn parse_words<W: Word>(values: &str) -> Option<Vec<W>> {
+    values.split(',').map(|word| word.parse().ok()).collect()
+}
+
+// Groups the non-zero words into runs of consecutive addresses, keeping short runs of zeros inline.
+fn memory_runs<W: Word>(memory: &Memory<W>) -> Vec<(usize, Vec<W>)> {
+    let mut runs: Vec<(usize, Vec<W>)> = Vec::new();
+    for (address, value) in memory.nonzero_words() {
+        match runs.last_mut() {
+            Some((start, words)) if address - (*start + words.len()) < MAX_ZERO_GAP => {
+                words.resize(address - *start, W::zero());
+                words.push(value.clone());
+            }
+            _ => runs.push((address, vec![value.clone()])),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::ComputerState;
+
+    fn load(text: &str) -> Result<Computer, SnapshotError> {
+        Computer::load(text.as_bytes())
+    }
+
+    fn save(computer: &Computer) -> String {
+        let mut text = Vec::new();
+        computer.save(&mut text).unwrap();
+        String::from_utf8(text).unwrap()
+    }
+
+    #[test]
+    fn round_trips_a_running_computer() {
+        // Reads two inputs, moves the relative base and stores them far apart.
+        let mut computer = Computer::new(vec![3, 100, 109, 1000, 203, 5, 4, 100, 99]);
+        computer.set_memory(1 << 40, -7);
+        for input in [11, 12, 13] {
+            computer.push_input(input);
+        }
+        computer.step().unwrap();
+        computer.step().unwrap();
+
+        let text = save(&computer);
+        assert_eq!(
+            text,
+            "intcode-snapshot 1
+ip 4
+relative-base 1000
+inputs 12,13
+memory 0 3,100,109,1000,203,5,4,100,99
+memory 100 11
+memory 1099511627776 -7
+"
+        );
+        let mut loaded = load(&text).unwrap();
+        assert_eq!(loaded.ip(), computer.ip());
+        assert_eq!(loaded.relative_base(), computer.relative_base());
+        assert_eq!(loaded.inputs, computer.inputs);
+        assert_eq!(loaded.memory(), computer.memory());
+        assert_eq!(save(&loaded), text);
+
+        assert_eq!(loaded.execute(), Ok(ComputerState::Output(11)));
+        assert_eq!(loaded.memory().get(1005), 12);
+        assert_eq!(loaded.inputs.len(), 1);
+    }
+
+    #[test]
+    fn ignores_comments_and_blank_lines() {
+        let computer = load(
+            "intcode-snapshot 1
+# saved by hand
+
+relative-base -2
+ip 1
+memory 3 5",
+        )
+        .unwrap();
+        assert_eq!(computer.ip(), 1);
+        assert_eq!(computer.relative_base(), -2);
+        assert!(computer.inputs.is_empty());
+        assert_eq!(computer.memory().get(3), 5);
+    }
+
+    fn rejects(text: &str) -> String {
+        load(text).map(|_| ()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn rejects_malformed_snapshots() {
+        assert_eq!(
+            rejects("intcode-snapshot 2\nip 0\nrelative-base 0"),
+            "unsupported snapshot header `intcode-snapshot 2`"
+        );
+        assert_eq!(rejects(""), "unsupported snapshot header ``");
+        assert_eq!(
+            rejects("intcode-snapshot 1\nrelative-base 0"),
+            "snapshot is missing `ip`"
+        );
+        assert_eq!(
+            rejects("intcode-snapshot 1\nip 0"),
+            "snapshot is missing `relative-base`"
+        );
+        assert_eq!(
+            rejects("intcode-snapshot 1\nip -1\nrelative-base 0"),
+            "line 2: invalid snapshot line `ip -1`"
+        );
+        assert_eq!(
+            rejects("intcode-snapshot 1\nip 0\nrelative-base 0\nmemory 0 1,x"),
+            "line 4: invalid snapshot line `memory 0 1,x`"
+        );
+        assert_eq!(
+            rejects("intcode-snapshot 1\nip 0 1\nrelative-base 0"),
+            "line 2: invalid snapshot line `ip 0 1`"
+        );
+        assert_eq!(
+            rejects("intcode-snapshot 1\nip 0\nrelative-base 0\nregisters 1"),
+            "line 4: invalid snapshot line `registers 1`"
+        );
+    }
+
+    #[test]
+    fn rejects_memory_past_the_last_address() {
+        let text = format!(
+            "intcode-snapshot 1\nip 0\nrelative-base 0\nmemory {} 1,2",
+            usize::MAX
+        );
+        assert_eq!(
+            rejects(&text),
+            format!("line 4: invalid snapshot line `memory {} 1,2`", usize::MAX)
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    observer::{StepEvent, StepObserver},
+    word::Word,
+};
+use std::io::{self, BufRead, Write};
+
+/// Writes one line per executed instruction to `writer`.
+///
+/// Each line holds the step number followed by the `StepEvent`: the instruction address, the
+/// decoded instruction, the resolved operands, the memory write with its old and new value, any
+/// input consumed or output produced, and the relative base afterwards:
+///
+/// ```text
+/// 17 42 ADD [r+1], #-3 -> [1000] | 12 -3 | [1000] 0 -> 9 | rb 999
+/// ```
+///
+/// Two traces can be compared with `first_divergence`.
+pub struct Tracer<W: Write> {
+    writer: W,
+    steps: u64,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> Tracer<W> {
+    pub fn new(writer: W) -> Tracer<W> {
+        Tracer {
+            writer,
+            steps: 0,
+            error: None,
+        }
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Flushes the trace and hands back the writer, reporting the first write error if any.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write, V: Word> StepObserver<V> for Tracer<W> {
+    fn on_step(&mut self, event: &StepEvent<V>) {
+        if self.error.is_none() {
+            if let Err(err) = writeln!(self.writer, "{} {}", self.steps, event) {
+                self.error = Some(err);
+            }
+        }
+        self.steps += 1;
+    }
+}
+
+/// Where two traces first differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// 1-based line number of the first differing line.
+    pub line: usize,
+    /// The differing lines, `None` when that trace ended early.
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+/// Compares two traces line by line and returns the first difference, if any.
+pub fn first_divergence<A: BufRead, B: BufRead>(
+    left: A,
+    right: B,
+) -> io::Result<Option<Divergence>> {
+    let mut left = left.lines();
+    let mut right = right.lines();
+    let mut line = 0;
+    loop {
+        line += 1;
+        let (a, b) = (left.next().transpose()?, right.next().transpose()?);
+        if a.is_none() && b.is_none() {
+            return Ok(None);
+        }
+        if a != b {
+            return Ok(Some(Divergence {
+                line,
+                left: a,
+                right: b,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assemble::assemble,
+        computer::{Computer, ComputerState},
+        error::IntcodeError,
+    };
+
+    // Runs `source` with `inputs` until it stops, returning the trace and how it ended.
+    fn trace(source: &str, inputs: &[i64]) -> (String, Result<ComputerState, IntcodeError>) {
+        let mut computer = Computer::new(assemble(source).unwrap());
+        for &input in inputs {
+            computer.push_input(input);
+        }
+        let mut tracer = Tracer::new(Vec::new());
+        let state = loop {
+            match computer.execute_with(&mut tracer) {
+                Ok(ComputerState::Output(_)) => {}
+                state => break state,
+            }
+        };
+        let trace = String::from_utf8(tracer.finish().unwrap()).unwrap();
+        (trace, state)
+    }
+
+    #[test]
+    fn writes_a_line_per_step() {
+        let (trace, state) = trace(
+            "       ARB #20
+                    IN -> [r+1]
+                    ADD [r+1], #-3 -> [x]
+                    OUT [x]
+                    HLT
+             x:     DATA 0",
+            &[8],
+        );
+        assert_eq!(state, Ok(ComputerState::Halt));
+        assert_eq!(
+            trace,
+            "0 0 ARB #20 | 20 | rb 20
+1 2 IN -> [r+1] | | [21] 0 -> 8 | in 8 | rb 20
+2 4 ADD [r+1], #-3 -> [11] | 8 -3 | [11] 0 -> 5 | rb 20
+3 8 OUT [11] | 5 | out 5 | rb 20
+4 10 HLT | | rb 20
+"
+        );
+    }
+
+    #[test]
+    fn stops_before_a_failing_instruction() {
+        let (trace, state) = trace(
+            "       ADD #1, #2 -> [x]
+                    MUL [x], [x] -> [-1]
+             x:     DATA 0",
+            &[],
+        );
+        assert_eq!(
+            state,
+            Err(IntcodeError::NegativeAddress {
+                ip: 4,
+                opcode: 2,
+                address: -1
+            })
+        );
+        // The failing instruction didn't execute, so it isn't traced.
+        assert_eq!(trace, "0 0 ADD #1, #2 -> [8] | 1 2 | [8] 0 -> 3 | rb 0\n");
+    }
+
+    fn divergence(left: &str, right: &str) -> Option<Divergence> {
+        first_divergence(left.as_bytes(), right.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn finds_the_first_divergence() {
+        let trace = "0 0 ARB #3 | 3 | rb 3\n1 2 HLT | | rb 3\n";
+        assert_eq!(divergence(trace, trace), None);
+        assert_eq!(divergence("", ""), None);
+        assert_eq!(
+            divergence(
+                trace,
+                "0 0 ARB #3 | 3 | rb 3\n1 2 OUT #1 | 1 | out 1 | rb 3\n"
+            ),
+            Some(Divergence {
+                line: 2,
+                left: Some("1 2 HLT | | rb 3".to_owned()),
+                right: Some("1 2 OUT #1 | 1 | out 1 | rb 3".to_owned()),
+            })
+        );
+        assert_eq!(
+            divergence(trace, "0 0 ARB #3 | 3 | rb 3\n"),
+            Some(Divergence {
+                line: 2,
+                left: Some("1 2 HLT | | rb 3".to_owned()),
+                right: None,
+            })
+        );
+        assert_eq!(
+            divergence("", trace),
+            Some(Divergence {
+                line: 1,
+                left: None,
+                right: Some("0 0 ARB #3 | 3 | rb 3".to_owned()),
+            })
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use crate::{
+    cfg::{control_flow_graph, BasicBlock},
+    disassemble::{disassemble, jump_outcomes, Disassembly},
+    instruction::{Instruction, Opcode, Parameter, ParameterMode},
+};
+use std::{collections::BTreeSet, fmt::Write};
+
+// Number of words per line in the generated program image.
+const WORDS_PER_LINE: usize = 12;
+
+/// Translates `program` to the source of a Rust module for `NativeComputer`.
+///
+/// Every basic block found by `control_flow_graph` becomes an arm of one big `match` on the
+/// instruction pointer, with memory accesses and arithmetic written out per instruction. The
+/// module defines `TRANSLATION` and a `computer()` function creating a `NativeComputer` for it,
+/// and only depends on the `intcode` crate. It can be included with `include!` or saved as a
+/// module file.
+///
+/// Parameters that the program itself writes to with a constant address, the usual way of
+/// indexing arrays in Intcode, are read from memory instead of being baked in. Anything else the
+/// translated code can't handle, from a write to an instruction to an overflow, hands the
+/// computer over to the interpreter before that instruction runs.
+pub fn translate(program: &[i64]) -> String {
+    let cfg = control_flow_graph(program);
+    let mut code = BTreeSet::new();
+    for (address, instruction) in cfg.blocks.values().flat_map(|block| &block.instructions) {
+        code.extend(*address..address + instruction.size());
+    }
+    let translator = Translator {
+        disassembly: disassemble(program),
+        code,
+        patched: cfg.patched_parameters(),
+    };
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Translated from a {} word Intcode program by intcode-translate. Do not edit.",
+        program.len()
+    )
+    .unwrap();
+    out.push_str(PRELUDE);
+
+    writeln!(out, "static PROGRAM: [i64; {}] = [", program.len()).unwrap();
+    for words in program.chunks(WORDS_PER_LINE) {
+        let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+        writeln!(out, "    {},", words.join(", ")).unwrap();
+    }
+    out.push_str("];\n\n");
+
+    let mut bits = vec![0u64; program.len().div_ceil(64)];
+    for address in translator.code.difference(&translator.patched) {
+        bits[address / 64] |= 1 << (address % 64);
+    }
+    writeln!(out, "static CODE: [u64; {}] = [", bits.len()).unwrap();
+    for bits in &bits {
+        writeln!(out, "    {:#018x},", bits).unwrap();
+    }
+    out.push_str("];\n\n");
+
+    out.push_str(RUN_START);
+    for block in cfg.blocks.values() {
+        translator.block(&mut out, block);
+    }
+    out.push_str(RUN_END);
+    out
+}
+
+const PRELUDE: &str = "
+use intcode::{ComputerState, IntcodeError, NativeComputer, Translation};
+
+pub static TRANSLATION: Translation = Translation {
+    program: &PROGRAM,
+    code: &CODE,
+    run,
+};
+
+pub fn computer() -> NativeComputer {
+    NativeComputer::new(&TRANSLATION)
+}
+
+";
+
+const RUN_START: &str = "#[allow(unreachable_code, unused_macros, unused_mut, clippy::all)]
+fn run(m: &mut NativeComputer, mut ip: usize, mut rb: i64) -> Result<ComputerState, IntcodeError> {
+    // Unwraps `$value`, or lets the interpreter take over at the instruction at `$ip`.
+    macro_rules! at {
+        ($ip:expr, $value:expr) => {
+            match $value {
+                Some(value) => value,
+                None => return m.fall_back($ip, rb),
+            }
+        };
+    }
+
+    loop {
+        match ip {
+";
+
+const RUN_END: &str = "            _ => {
+                if let Some(state) = m.interpret(ip, rb) {
+                    return state;
+                }
+                ip = m.ip();
+                rb = m.relative_base();
+            }
+        }
+    }
+}
+";
+
+struct Translator {
+    disassembly: Disassembly,
+    // Every word of a translated instruction.
+    code: BTreeSet<usize>,
+    // Parameter words that instructions write to with a constant address, the way programs
+    // index arrays.
+    patched: BTreeSet<usize>,
+}
+
+// Outcome of translating one instruction.
+enum Flow {
+    Continue,
+    // The instruction always leaves the block, so nothing after it is reached.
+    Leave,
+}
+
+impl Translator {
+    // Translates a block into one or more arms. Computers resume right at an input that had to
+    // wait and right after an output, so those addresses start arms of their own.
+    fn block(&self, out: &mut String, block: &BasicBlock) {
+        writeln!(out, "            {} => {{", block.start).unwrap();
+        let mut left = false;
+        for (address, instruction) in &block.instructions {
+            if left || (instruction.opcode == Opcode::Input && *address != block.start) {
+                if !left {
+                    writeln!(out, "                ip = {};", address).unwrap();
+                }
+                writeln!(out, "            }}\n            {} => {{", address).unwrap();
+            }
+            writeln!(
+                out,
+                "                // {:>5}  {}",
+                address,
+                self.disassembly.format_instruction(instruction)
+            )
+            .unwrap();
+            let mut body = Vec::new();
+            let flow = self.instruction(&mut body, *address, instruction);
+            out.push_str("                {\n");
+            for line in body {
+                writeln!(out, "                    {}", line).unwrap();
+            }
+            out.push_str("                }\n");
+            left = match flow {
+                Flow::Continue => false,
+                Flow::Leave if instruction.opcode == Opcode::Output => true,
+                Flow::Leave => break,
+            };
+        }
+        if !left {
+            writeln!(out, "                ip = {};", block.end()).unwrap();
+        }
+        out.push_str("            }\n");
+    }
+
+    fn instruction(&self, body: &mut Vec<String>, ip: usize, instruction: &Instruction) -> Flow {
+        let next = ip + instruction.size();
+        let params = instruction.parameters();
+        match instruction.opcode {
+            Opcode::Add | Opcode::Mul | Opcode::LessThan | Opcode::Equals => {
+                // Any failure hands over before the instruction has done anything, so the
+                // order of the checks doesn't matter.
+                let target = match self.target(body, ip, instruction) {
+                    Some(target) => target,
+                    None => return fall_back(body, ip),
+                };
+                let a = match self.read(body, ip, instruction, 0, "a") {
+                    Some(a) => a,
+                    None => return fall_back(body, ip),
+                };
+                let b = match self.read(body, ip, instruction, 1, "b") {
+                    Some(b) => b,
+                    None => return fall_back(body, ip),
+                };
+                let value = match instruction.opcode {
+                    Opcode::Add => format!("at!({}, {}.checked_add({}))", ip, a, b),
+                    Opcode::Mul => format!("at!({}, {}.checked_mul({}))", ip, a, b),
+                    Opcode::LessThan => format!("({} < {}) as i64", a, b),
+                    _ => format!("({} == {}) as i64", a, b),
+                };
+                body.push(format!("let value: i64 = {};", value));
+                body.push(format!("m.set({}, value);", target));
+            }
+            Opcode::Input => {
+                let target = match self.target(body, ip, instruction) {
+                    Some(target) => target,
+                    None => return fall_back(body, ip),
+                };
+                body.push("let value = match m.input() {".to_owned());
+                body.push("    Some(value) => value,".to_owned());
+                body.push(format!(
+                    "    None => return m.pause({}, rb, ComputerState::InputRequired),",
+                    ip
+                ));
+                body.push("};".to_owned());
+                body.push(format!("m.set({}, value);", target));
+            }
+            Opcode::Output => {
+                let value = match self.read(body, ip, instruction, 0, "value") {
+                    Some(value) => value,
+                    None => return fall_back(body, ip),
+                };
+                body.push(format!(
+                    "return m.pause({}, rb, ComputerState::Output({}));",
+                    next, value
+                ));
+                return Flow::Leave;
+            }
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                // A condition the program writes to can go either way.
+                let (taken, falls_through) = if self.patched.contains(&(ip + 1)) {
+                    (true, true)
+                } else {
+                    jump_outcomes(instruction)
+                };
+                if !taken {
+                    return Flow::Continue;
+                }
+                let mut jump = Vec::new();
+                let target = &params[1];
+                if target.mode == ParameterMode::Immediate
+                    && target.value >= 0
+                    && !self.patched.contains(&(ip + 2))
+                {
+                    jump.push(format!("ip = {};", target.value));
+                    jump.push("continue;".to_owned());
+                } else {
+                    match self.read(&mut jump, ip, instruction, 1, "target") {
+                        Some(target) => {
+                            jump.push(format!("ip = at!({}, m.address({}));", ip, target));
+                            jump.push("continue;".to_owned());
+                        }
+                        None => {
+                            fall_back(&mut jump, ip);
+                        }
+                    }
+                }
+                if !falls_through {
+                    body.extend(jump);
+                    return Flow::Leave;
+                }
+                let condition = match self.read(body, ip, instruction, 0, "condition") {
+                    Some(condition) => condition,
+                    None => return fall_back(body, ip),
+                };
+                let test = if instruction.opcode == Opcode::JumpIfTrue {
+                    "!="
+                } else {
+                    "=="
+                };
+                body.push(format!("if {} {} 0 {{", condition, test));
+                body.extend(jump.into_iter().map(|line| format!("    {}", line)));
+                body.push("}".to_owned());
+            }
+            Opcode::AdjustRelativeBase => {
+                let offset = match self.read(body, ip, instruction, 0, "offset") {
+                    Some(offset) => offset,
+                    None => return fall_back(body, ip),
+                };
+                body.push(format!("rb = at!({}, rb.checked_add({}));", ip, offset));
+            }
+            Opcode::Halt => {
+                body.push(format!("return m.pause({}, rb, ComputerState::Halt);", ip));
+                return Flow::Leave;
+            }
+        }
+        Flow::Continue
+    }
+
+    // The word a parameter of the instruction at `ip` holds. Words the program writes to are
+    // read from memory, the rest are constants.
+    fn word(&self, ip: usize, index: usize, parameter: &Parameter) -> String {
+        let address = ip + index + 1;
+        if self.patched.contains(&address) {
+            format!("m.get({})", address)
+        } else {
+            parameter.value.to_string()
+        }
+    }
+
+    // Returns an expression for the value of a parameter, or `None` if reading it always fails.
+    fn read(
+        &self,
+        body: &mut Vec<String>,
+        ip: usize,
+        instruction: &Instruction,
+        index: usize,
+        name: &str,
+    ) -> Option<String> {
+        let parameter = &instruction.parameters()[index];
+        let patched = self.patched.contains(&(ip + index + 1));
+        let value = match parameter.mode {
+            ParameterMode::Immediate if patched => format!("m.get({})", ip + index + 1),
+            ParameterMode::Immediate => format!("{}i64", parameter.value),
+            ParameterMode::Position if patched => {
+                body.push(format!(
+                    "let {}_address = at!({}, m.address(m.get({})));",
+                    name,
+                    ip,
+                    ip + index + 1
+                ));
+                format!("m.get({}_address)", name)
+            }
+            ParameterMode::Position if parameter.value < 0 => return None,
+            ParameterMode::Position => format!("m.get({})", parameter.value),
+            ParameterMode::Relative => {
+                body.push(format!(
+                    "let {}_address = at!({}, m.relative(rb, {}));",
+                    name,
+                    ip,
+                    self.word(ip, index, parameter)
+                ));
+                format!("m.get({}_address)", name)
+            }
+        };
+        body.push(format!("let {} = {};", name, value));
+        Some(name.to_owned())
+    }
+
+    // Returns an expression for the address the last parameter writes to, or `None` if the
+    // write is known to fail or to change the program.
+    fn target(
+        &self,
+        body: &mut Vec<String>,
+        ip: usize,
+        instruction: &Instruction,
+    ) -> Option<String> {
+        let index = instruction.parameters().len() - 1;
+        let parameter = &instruction.parameters()[index];
+        let patched = self.patched.contains(&(ip + index + 1));
+        let address = match parameter.mode {
+            ParameterMode::Relative => {
+                format!("m.relative(rb, {})", self.word(ip, index, parameter))
+            }
+            _ if patched => format!("m.address(m.get({}))", ip + index + 1),
+            _ => {
+                let address = parameter.value;
+                if address < 0 || self.protected(address as usize) {
+                    return None;
+                }
+                return Some(address.to_string());
+            }
+        };
+        body.push(format!("let target = at!({}, {});", ip, address));
+        body.push("if m.is_code(target) {".to_owned());
+        body.push(format!("    return m.fall_back({}, rb);", ip));
+        body.push("}".to_owned());
+        Some("target".to_owned())
+    }
+
+    // Whether a write to `address` changes the translated code.
+    fn protected(&self, address: usize) -> bool {
+        self.code.contains(&address) && !self.patched.contains(&address)
+    }
+}
+
+// Ends the instruction at `ip` by handing over to the interpreter.
+fn fall_back(body: &mut Vec<String>, ip: usize) -> Flow {
+    body.push(format!("return m.fall_back({}, rb);", ip));
+    Flow::Leave
+}
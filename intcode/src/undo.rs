@@ -0,0 +1,241 @@
+use crate::{
+    computer::Computer,
+    instruction::Opcode,
+    observer::{StepEvent, StepObserver},
+    word::Word,
+};
+use std::collections::VecDeque;
+
+/// What it takes to put the computer back in the state before one executed instruction.
+#[derive(Debug, Clone)]
+struct Undo<W> {
+    ip: usize,
+    relative_base: i64,
+    write: Option<(usize, W)>,
+    input: Option<W>,
+}
+
+/// Records the last `limit` executed instructions so `Computer::step_back` can undo them.
+///
+/// Each entry holds the ip and relative base before the instruction, the old value of the word
+/// it wrote and the input it consumed. Once the log is full the oldest entry is dropped. Outputs
+/// can't be taken back; stepping back over an `OUT` only rewinds the computer.
+#[derive(Debug, Clone)]
+pub struct UndoLog<W = i64> {
+    entries: VecDeque<Undo<W>>,
+    limit: usize,
+}
+
+impl<W: Word> UndoLog<W> {
+    pub fn new(limit: usize) -> UndoLog<W> {
+        UndoLog {
+            entries: VecDeque::new(),
+            limit,
+        }
+    }
+
+    /// Number of instructions that can currently be undone.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Changes how many instructions are kept, dropping the oldest ones if there are too many.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        while self.entries.len() > limit {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// How many instructions have to be undone to get back to just before the most recent
+    /// logged write to `address`.
+    pub fn steps_since_write(&self, address: usize) -> Option<usize> {
+        self.entries
+            .iter()
+            .rev()
+            .position(|undo| matches!(undo.write, Some((written, _)) if written == address))
+            .map(|position| position + 1)
+    }
+}
+
+impl<W: Word> StepObserver<W> for UndoLog<W> {
+    fn on_step(&mut self, event: &StepEvent<W>) {
+        // A halt changes nothing, and is executed again every time a halted computer is resumed.
+        if self.limit == 0 || event.instruction.opcode == Opcode::Halt {
+            return;
+        }
+        let mut relative_base = event.relative_base;
+        if event.instruction.opcode == Opcode::AdjustRelativeBase {
+            // The adjustment succeeded, so undoing it can't overflow.
+            relative_base -= event.operands()[0].to_i64().unwrap_or(0);
+        }
+        if self.entries.len() == self.limit {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Undo {
+            ip: event.ip,
+            relative_base,
+            write: event
+                .write
+                .as_ref()
+                .map(|write| (write.address, write.old.clone())),
+            input: event.input.clone(),
+        });
+    }
+}
+
+impl<W: Word> Computer<W> {
+    /// Undoes the most recent instruction recorded in `log`, putting back the memory word it
+    /// wrote and the input it consumed. Returns `false` when the log is empty.
+    ///
+    /// The log only matches the computer if it observed every step since it was created or
+    /// cleared.
+    pub fn step_back(&mut self, log: &mut UndoLog<W>) -> bool {
+        let undo = match log.entries.pop_back() {
+            Some(undo) => undo,
+            None => return false,
+        };
+        if let Some((address, old)) = undo.write {
+            self.set_memory(address, old);
+        }
+        if let Some(input) = undo.input {
+            // The next input is taken from the back of the queue.
+            self.inputs.push_back(input);
+        }
+        self.set_position(undo.ip, undo.relative_base);
+        true
+    }
+
+    /// Steps back to just before the most recent logged write to `address`. Returns the number
+    /// of instructions undone, or `None` without changing anything if the log holds no such
+    /// write.
+    pub fn run_back_to_write(&mut self, log: &mut UndoLog<W>, address: usize) -> Option<usize> {
+        let steps = log.steps_since_write(address)?;
+        for _ in 0..steps {
+            self.step_back(log);
+        }
+        Some(steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assemble::assemble,
+        computer::{ComputerState, StepResult},
+    };
+
+    // Reads two values into a frame on the relative base, outputs their sum and halts.
+    fn adder() -> Computer {
+        let program = assemble(
+            "        ARB #frame
+                     IN -> [r]
+                     IN -> [r+1]
+                     ADD [r], [r+1] -> [sum]
+                     OUT [sum]
+                     ARB #-3
+                     ADD [sum], #1 -> [sum]
+                     HLT
+             sum:    DATA 0
+             frame:  DATA 0, 0",
+        )
+        .unwrap();
+        let mut computer = Computer::new(program);
+        computer.push_input(3);
+        computer.push_input(4);
+        computer
+    }
+
+    fn same(a: &Computer, b: &Computer) -> bool {
+        a.ip() == b.ip()
+            && a.relative_base() == b.relative_base()
+            && a.inputs == b.inputs
+            && a.memory() == b.memory()
+    }
+
+    #[test]
+    fn steps_back_to_every_earlier_state() {
+        let mut computer = adder();
+        let mut log = UndoLog::new(100);
+        let mut states = vec![computer.clone()];
+        while computer.step_with(&mut log).unwrap() != StepResult::Stop {
+            states.push(computer.clone());
+        }
+        assert_eq!(computer.memory().get(19), 8);
+        // The halt isn't logged.
+        assert_eq!(log.len(), states.len() - 1);
+        assert!(same(&computer, states.last().unwrap()));
+
+        states.pop();
+        while let Some(state) = states.pop() {
+            assert!(computer.step_back(&mut log));
+            assert!(same(&computer, &state), "state before ip {}", state.ip());
+        }
+        assert!(!computer.step_back(&mut log));
+        assert!(same(&computer, &adder()));
+
+        // Running forward again gives the same result.
+        assert_eq!(computer.execute(), Ok(ComputerState::Output(7)));
+    }
+
+    #[test]
+    fn runs_back_to_the_last_write() {
+        let mut computer = adder();
+        let mut log = UndoLog::new(100);
+        while computer.step_with(&mut log).unwrap() != StepResult::Stop {}
+
+        // `sum` was written by the ADD at 14, after it had been set by the ADD at 6.
+        assert_eq!(log.steps_since_write(19), Some(1));
+        assert_eq!(computer.run_back_to_write(&mut log, 19), Some(1));
+        assert_eq!(computer.ip(), 14);
+        assert_eq!(computer.memory().get(19), 7);
+        assert_eq!(computer.run_back_to_write(&mut log, 19), Some(3));
+        assert_eq!(computer.ip(), 6);
+        assert_eq!(computer.memory().get(19), 0);
+        assert_eq!(computer.relative_base(), 20);
+
+        let before = computer.clone();
+        assert_eq!(computer.run_back_to_write(&mut log, 19), None);
+        assert_eq!(computer.run_back_to_write(&mut log, 1_000), None);
+        assert!(same(&computer, &before));
+    }
+
+    #[test]
+    fn keeps_only_the_newest_entries() {
+        let mut computer = adder();
+        let mut log = UndoLog::new(2);
+        while computer.step_with(&mut log).unwrap() != StepResult::Stop {}
+        assert_eq!(log.len(), 2);
+        assert!(computer.step_back(&mut log));
+        assert!(computer.step_back(&mut log));
+        assert!(!computer.step_back(&mut log));
+        assert_eq!(computer.ip(), 12);
+
+        // Lowering the limit drops the oldest entries.
+        let mut computer = adder();
+        let mut log = UndoLog::new(10);
+        while computer.step_with(&mut log).unwrap() != StepResult::Stop {}
+        log.set_limit(3);
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.steps_since_write(19), Some(1));
+        assert_eq!(log.steps_since_write(20), None);
+
+        let mut computer = adder();
+        let mut log = UndoLog::new(0);
+        while computer.step_with(&mut log).unwrap() != StepResult::Stop {}
+        assert!(log.is_empty());
+    }
+}
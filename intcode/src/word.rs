@@ -0,0 +1,188 @@
+use std::{
+    convert::TryFrom,
+    fmt::{Debug, Display},
+    hash::Hash,
+    str::FromStr,
+};
+
+/// A value a `Computer` can hold in memory and compute with.
+///
+/// Arithmetic is checked: an `ADD` or `MUL` whose result doesn't fit stops the computer with
+/// `IntcodeError::Overflow` rather than wrapping. `i64` is the default and what every day uses.
+/// `i128` gives more headroom, and with the `bigint` feature `num_bigint::BigInt` never overflows.
+pub trait Word: Clone + Debug + Display + FromStr + Eq + Ord + Hash {
+    fn from_i64(value: i64) -> Self;
+
+    /// The value as an `i64`, or `None` when it doesn't fit.
+    fn to_i64(&self) -> Option<i64>;
+
+    fn is_zero(&self) -> bool;
+
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    fn zero() -> Self {
+        Self::from_i64(0)
+    }
+}
+
+impl Word for i64 {
+    #[inline]
+    fn from_i64(value: i64) -> i64 {
+        value
+    }
+
+    #[inline]
+    fn to_i64(&self) -> Option<i64> {
+        Some(*self)
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+
+    #[inline]
+    fn checked_add(&self, other: &i64) -> Option<i64> {
+        i64::checked_add(*self, *other)
+    }
+
+    #[inline]
+    fn checked_mul(&self, other: &i64) -> Option<i64> {
+        i64::checked_mul(*self, *other)
+    }
+}
+
+impl Word for i128 {
+    #[inline]
+    fn from_i64(value: i64) -> i128 {
+        i128::from(value)
+    }
+
+    #[inline]
+    fn to_i64(&self) -> Option<i64> {
+        i64::try_from(*self).ok()
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+
+    #[inline]
+    fn checked_add(&self, other: &i128) -> Option<i128> {
+        i128::checked_add(*self, *other)
+    }
+
+    #[inline]
+    fn checked_mul(&self, other: &i128) -> Option<i128> {
+        i128::checked_mul(*self, *other)
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl Word for num_bigint::BigInt {
+    fn from_i64(value: i64) -> num_bigint::BigInt {
+        num_bigint::BigInt::from(value)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        i64::try_from(self).ok()
+    }
+
+    fn is_zero(&self) -> bool {
+        self.sign() == num_bigint::Sign::NoSign
+    }
+
+    fn checked_add(&self, other: &num_bigint::BigInt) -> Option<num_bigint::BigInt> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &num_bigint::BigInt) -> Option<num_bigint::BigInt> {
+        Some(self * other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        computer::{parse_words, Computer, ComputerState},
+        error::IntcodeError,
+    };
+
+    // Multiplies 2^62 by 4 and adds 1, then outputs the result.
+    const PAST_I64: &str = "1102,4611686018427387904,4,11,1001,11,1,11,4,11,99,0";
+
+    // Runs `program` to the end, returning its outputs.
+    fn run<W: Word>(program: &str) -> Result<Vec<W>, IntcodeError<W>> {
+        let mut computer = Computer::new(parse_words::<W>(program));
+        let mut outputs = Vec::new();
+        loop {
+            match computer.execute()? {
+                ComputerState::Output(output) => outputs.push(output),
+                ComputerState::Halt => return Ok(outputs),
+                state => panic!("unexpected state {:?}", state),
+            }
+        }
+    }
+
+    #[test]
+    fn i64_overflows() {
+        assert_eq!(
+            run::<i64>(PAST_I64),
+            Err(IntcodeError::Overflow {
+                ip: 0,
+                opcode: 1102
+            })
+        );
+    }
+
+    #[test]
+    fn i128_goes_past_i64() {
+        let expected = (1i128 << 64) + 1;
+        assert_eq!(run::<i128>(PAST_I64), Ok(vec![expected]));
+        assert_eq!(expected.to_i64(), None);
+    }
+
+    #[test]
+    fn i128_addresses_must_fit_usize() {
+        let address = 1i128 << 70;
+        assert_eq!(
+            run::<i128>(&format!("4,{},99", address)),
+            Err(IntcodeError::AddressTooLarge {
+                ip: 0,
+                opcode: 4,
+                address
+            })
+        );
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn bigint_never_overflows() {
+        use num_bigint::BigInt;
+
+        let expected: BigInt = (BigInt::from(1) << 64u32) + 1u8;
+        assert_eq!(run::<BigInt>(PAST_I64), Ok(vec![expected.clone()]));
+        let squared = format!("2,7,7,7,4,7,99,{}", expected);
+        assert_eq!(run::<BigInt>(&squared), Ok(vec![&expected * &expected]));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn bigint_addresses_must_fit_usize() {
+        use num_bigint::BigInt;
+
+        let address: BigInt = BigInt::from(1) << 100u32;
+        assert_eq!(
+            run::<BigInt>(&format!("4,{},99", address)),
+            Err(IntcodeError::AddressTooLarge {
+                ip: 0,
+                opcode: BigInt::from(4),
+                address
+            })
+        );
+    }
+}
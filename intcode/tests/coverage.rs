@@ -0,0 +1,102 @@
+//! Coverage of small programs and of a recorded day 13 game.
+
+use intcode::{parse_program, BranchCount, Computer, Coverage, Recording};
+
+// Outputs 1 if the input is 8, 0 otherwise, with an `EQ`.
+const EQUALS_8: &str = "3,9,8,9,10,9,4,9,99,-1,8";
+
+// Outputs 0 if the input is 0, 1 otherwise, with a `JF`.
+const NON_ZERO: &str = "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9";
+
+fn run(program: &str, input: i64, coverage: &mut Coverage) {
+    let mut computer = Computer::new(parse_program(program));
+    computer.push_input(input);
+    while let intcode::ComputerState::Output(_) = computer.execute_with(coverage).unwrap() {}
+}
+
+#[test]
+fn counts_branch_outcomes() {
+    let mut coverage = Coverage::new();
+    run(EQUALS_8, 8, &mut coverage);
+    let mut other = Coverage::new();
+    run(EQUALS_8, 3, &mut other);
+    run(EQUALS_8, 4, &mut other);
+    coverage.merge(&other);
+
+    assert_eq!(coverage.hits(0), 3);
+    assert_eq!(coverage.hits(1), 0);
+    assert_eq!(
+        coverage.branch(2),
+        Some(BranchCount {
+            taken: 1,
+            not_taken: 2
+        })
+    );
+    assert_eq!(coverage.branch(0), None);
+
+    let mut coverage = Coverage::new();
+    run(NON_ZERO, 0, &mut coverage);
+    assert_eq!(
+        coverage.branch(2),
+        Some(BranchCount {
+            taken: 1,
+            not_taken: 0
+        })
+    );
+    // Jumped over the `ADD`.
+    assert_eq!(coverage.hits(5), 0);
+}
+
+#[test]
+fn writes_report_and_lcov() {
+    let program = parse_program(NON_ZERO);
+    let mut coverage = Coverage::new();
+    run(NON_ZERO, 0, &mut coverage);
+
+    let mut report = Vec::new();
+    coverage.write_report(&program, &mut report).unwrap();
+    let report = String::from_utf8(report).unwrap();
+    assert!(report.starts_with("Instructions: 4 of 5 executed (80.00%)\n"));
+    assert!(report.contains("Branches: 1 of 2 outcomes covered (50.00%)\n"));
+    assert!(report.contains("[taken 1, not taken 0]"));
+
+    let mut lcov = Vec::new();
+    coverage
+        .write_lcov(&program, "non_zero.dis", &mut lcov)
+        .unwrap();
+    let lcov = String::from_utf8(lcov).unwrap();
+    assert!(lcov
+        .starts_with("TN:\nSF:non_zero.dis\nDA:1,1\nDA:2,1\nBRDA:2,0,0,1\nBRDA:2,0,1,0\nDA:3,0\n"));
+    assert!(lcov.ends_with("BRF:2\nBRH:1\nLF:5\nLH:4\nend_of_record\n"));
+}
+
+#[test]
+fn day13_never_loses_the_ball() {
+    let recording = Recording::load(&include_bytes!("golden/day13.txt")[..]).unwrap();
+    let mut computer = Computer::new(parse_program(include_str!("../../day13/input.txt")));
+    computer.set_memory(0, 2);
+    let mut coverage = Coverage::new();
+    recording.replay_with(computer, &mut coverage).unwrap();
+
+    // Every move checks whether the ball dropped below the paddle...
+    assert!(coverage.hits(365) > 0);
+    // ...but the recorded game never gets to the game over screen that follows.
+    assert_eq!(coverage.hits(372), 0);
+}
+
+#[test]
+fn counts_code_at_high_addresses() {
+    let high = 1 << 40;
+    let mut coverage = Coverage::new();
+    for _ in 0..2 {
+        let mut computer = Computer::new(vec![1105, 1, high as i64]);
+        computer.set_memory(high, 99);
+        computer.execute_with(&mut coverage).unwrap();
+    }
+    let mut merged = Coverage::new();
+    merged.merge(&coverage);
+    merged.merge(&coverage);
+    assert_eq!(merged.hits(0), 4);
+    assert_eq!(merged.hits(high), 4);
+    assert_eq!(merged.hits(high - 1), 0);
+}
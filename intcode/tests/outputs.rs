@@ -0,0 +1,56 @@
+//! Batched output helpers, on tiny hand-written programs.
+
+use intcode::{Computer, ComputerState, OutputError};
+
+// Outputs 1, 2 and 3, then halts.
+const COUNT: &[i64] = &[104, 1, 104, 2, 104, 3, 99];
+
+// Outputs 7, reads a word into address 0, outputs it, then halts.
+const ECHO: &[i64] = &[104, 7, 3, 0, 4, 0, 99];
+
+#[test]
+fn take_outputs_reports_partial_record() {
+    let mut computer = Computer::new(COUNT.to_vec());
+    assert_eq!(computer.take_outputs(), Ok([1, 2]));
+    assert_eq!(
+        computer.take_outputs::<2>(),
+        Err(OutputError::Halted { outputs: vec![3] })
+    );
+}
+
+#[test]
+fn run_until_input_collects_outputs() {
+    let mut computer = Computer::new(ECHO.to_vec());
+    assert_eq!(computer.run_until_input(), Ok(vec![7]));
+    computer.push_input(42);
+    assert_eq!(
+        computer.run_until_input(),
+        Err(OutputError::Halted { outputs: vec![42] })
+    );
+}
+
+#[test]
+fn output_chunks_stop_between_records() {
+    let mut computer = Computer::new(ECHO.to_vec());
+    let mut chunks = computer.output_chunks::<1>();
+    assert_eq!(chunks.next(), Some(Ok([7])));
+    assert_eq!(chunks.next(), None);
+    assert_eq!(chunks.stopped(), Some(&ComputerState::InputRequired));
+
+    let mut computer = Computer::new(COUNT.to_vec());
+    let mut chunks = computer.output_chunks::<2>();
+    assert_eq!(chunks.next(), Some(Ok([1, 2])));
+    assert_eq!(
+        chunks.next(),
+        Some(Err(OutputError::Halted { outputs: vec![3] }))
+    );
+    assert_eq!(chunks.next(), None);
+    assert_eq!(chunks.stopped(), None);
+}
+
+#[test]
+#[should_panic(expected = "Output chunks must hold at least one value")]
+fn output_chunks_reject_empty_records() {
+    let mut computer = Computer::new(COUNT.to_vec());
+    computer.output_chunks::<0>();
+}
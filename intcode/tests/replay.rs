@@ -0,0 +1,115 @@
+//! Replays recorded runs of the days that drive a program interactively, and checks the programs
+//! still produce exactly the recorded outputs. The recordings were made with
+//! `INTCODE_RECORD=../intcode/tests/golden/dayNN.txt cargo run` in the day's directory.
+
+use intcode::{parse_program, Computer, ComputerState, IoEvent, Recording, ReplayError};
+
+fn golden(recording: &str) -> Recording {
+    Recording::load(recording.as_bytes()).unwrap()
+}
+
+/// Replays `recording` on `program`, with the memory changes the day makes before running it.
+fn replay(program: &str, patches: &[(usize, i64)], recording: &Recording) -> Computer {
+    let mut computer = Computer::new(parse_program(program));
+    for &(address, value) in patches {
+        computer.set_memory(address, value);
+    }
+    recording.replay(computer).unwrap()
+}
+
+#[test]
+fn day13_arcade() {
+    let recording = golden(include_str!("golden/day13.txt"));
+    replay(include_str!("../../day13/input.txt"), &[(0, 2)], &recording);
+    // The last output is the final score.
+    assert_eq!(recording.outputs().last(), Some(&16309));
+}
+
+#[test]
+fn day15_droid() {
+    let recording = golden(include_str!("golden/day15.txt"));
+    replay(include_str!("../../day15/input.txt"), &[], &recording);
+    assert_eq!(recording.outputs().last(), Some(&2));
+}
+
+#[test]
+fn day17_vacuum_robot() {
+    let recording = golden(include_str!("golden/day17.txt"));
+    replay(include_str!("../../day17/input.txt"), &[(0, 2)], &recording);
+    assert_eq!(recording.outputs().last(), Some(&1_045_393));
+}
+
+#[test]
+fn recording_round_trips() {
+    let mut computer = Computer::new(parse_program(include_str!("../../day15/input.txt")));
+    computer.start_recording();
+    for &direction in &[1, 2, 3, 4, 4] {
+        computer.push_input(direction);
+        computer.execute().unwrap();
+    }
+    let recording = computer.take_recording().unwrap();
+    assert_eq!(recording.inputs().count(), 5);
+    assert_eq!(recording.outputs().count(), 5);
+
+    let mut saved = Vec::new();
+    recording.save(&mut saved).unwrap();
+    assert_eq!(Recording::load(&saved[..]).unwrap(), recording);
+}
+
+#[test]
+fn replay_detects_mismatch() {
+    let mut recording = golden(include_str!("golden/day15.txt"));
+    let index = recording
+        .events
+        .iter()
+        .position(|event| matches!(event, IoEvent::Output(_)))
+        .unwrap();
+    recording.events[index] = IoEvent::Output(7);
+
+    let computer = Computer::new(parse_program(include_str!("../../day15/input.txt")));
+    match recording.replay(computer).unwrap_err() {
+        ReplayError::Mismatch {
+            index: at,
+            expected,
+            actual: ComputerState::Output(_),
+        } => {
+            assert_eq!(at, index);
+            assert_eq!(expected, IoEvent::Output(7));
+        }
+        err => panic!("Unexpected replay error {:?}", err),
+    }
+}
+
+#[test]
+fn clones_record_on_their_own() {
+    let mut computer = Computer::new(parse_program(include_str!("../../day15/input.txt")));
+    computer.start_recording();
+    computer.push_input(1);
+    computer.execute().unwrap();
+
+    let mut clone = computer.clone();
+    clone.push_input(2);
+    clone.execute().unwrap();
+    computer.push_input(3);
+    computer.execute().unwrap();
+
+    let inputs = |computer: &Computer| -> Vec<i64> {
+        computer.recording().unwrap().inputs().copied().collect()
+    };
+    assert_eq!(inputs(&computer), [1, 3]);
+    assert_eq!(inputs(&clone), [1, 2]);
+    assert_eq!(clone.recording().unwrap().events.len(), 4);
+}
+
+#[test]
+fn halt_is_recorded_once() {
+    let mut computer = Computer::new(vec![104i64, 5, 99]);
+    computer.start_recording();
+    for _ in 0..4 {
+        computer.execute().unwrap();
+    }
+    assert_eq!(
+        computer.take_recording().unwrap().events,
+        [IoEvent::Output(5), IoEvent::Halt]
+    );
+}
@@ -0,0 +1,76 @@
+//! Compares execution speed of the interpreter and the translated programs.
+//!
+//! Run with `cargo bench --bench native`.
+
+use intcode::{Computer, ComputerState, NativeComputer, Translation};
+use std::time::{Duration, Instant};
+use translated::{day09, day19};
+
+const ROUNDS: usize = 20;
+
+fn main() {
+    compare("day09 part 2", |native| boost(&day09::TRANSLATION, native));
+    compare("day19 50x50 scan", |native| {
+        scan(&day19::TRANSLATION, native)
+    });
+}
+
+// Runs the workload ROUNDS times in each mode and reports the fastest run of each.
+fn compare<F: FnMut(bool) -> i64>(name: &str, mut workload: F) {
+    let mut best = |native: bool| {
+        let mut result = 0;
+        let mut best = Duration::from_secs(u64::MAX);
+        for _ in 0..ROUNDS {
+            let start = Instant::now();
+            result = workload(native);
+            best = best.min(start.elapsed());
+        }
+        (result, best)
+    };
+    let (interpreted_result, interpreted) = best(false);
+    let (native_result, native) = best(true);
+    assert_eq!(
+        interpreted_result, native_result,
+        "{} gave different results",
+        name
+    );
+    println!(
+        "{:<26} interpreted {:>10.2?}  native {:>10.2?}  speedup {:.2}x",
+        name,
+        interpreted,
+        native,
+        interpreted.as_secs_f64() / native.as_secs_f64()
+    );
+}
+
+// Runs a fresh computer for the program with `inputs` until its first output.
+fn run(translation: &'static Translation, native: bool, inputs: &[i64]) -> i64 {
+    let state = if native {
+        let mut computer = NativeComputer::new(translation);
+        inputs.iter().for_each(|input| computer.push_input(*input));
+        computer.execute()
+    } else {
+        let mut computer = Computer::new(translation.program.to_vec());
+        computer.warm_decode_cache();
+        inputs.iter().for_each(|input| computer.push_input(*input));
+        computer.execute()
+    };
+    match state.unwrap() {
+        ComputerState::Output(output) => output,
+        state => panic!("expected output, got {:?}", state),
+    }
+}
+
+fn boost(translation: &'static Translation, native: bool) -> i64 {
+    run(translation, native, &[2])
+}
+
+fn scan(translation: &'static Translation, native: bool) -> i64 {
+    let mut affected = 0;
+    for y in 0..50 {
+        for x in 0..50 {
+            affected += run(translation, native, &[x, y]);
+        }
+    }
+    affected
+}
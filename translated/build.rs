@@ -0,0 +1,40 @@
+use intcode::{parse_program, translate};
+use std::{env, fs, path::PathBuf};
+
+// Every day with an Intcode program as its input.
+const DAYS: [&str; 10] = [
+    "day02", "day05", "day07", "day09", "day11", "day13", "day15", "day17", "day19", "day23",
+];
+
+// Memory changes made before running a program, as `(address, value)`.
+type Patches = &'static [(usize, i64)];
+
+// Programs that a day patches before running them, by name, day and patches. Patching an
+// instruction of a translated program makes it fall back to the interpreter, so these are
+// translated again with the patches applied.
+const PATCHED: [(&str, &str, Patches); 2] = [
+    // Playing for free.
+    ("day13_part2", "day13", &[(0, 2)]),
+    // Waking up the robot.
+    ("day17_part2", "day17", &[(0, 2)]),
+];
+
+fn main() {
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    let translations = DAYS
+        .iter()
+        .map(|day| (*day, *day, &[][..]))
+        .chain(PATCHED.iter().copied());
+    for (name, day, patches) in translations {
+        let path = format!("../{}/input.txt", day);
+        println!("cargo:rerun-if-changed={}", path);
+        let input = fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("Could not read {}: {}", path, err));
+        let mut program = parse_program(&input);
+        for &(address, value) in patches {
+            program[address] = value;
+        }
+        let source = translate(&program);
+        fs::write(out_dir.join(format!("{}.rs", name)), source).unwrap();
+    }
+}
@@ -0,0 +1,34 @@
+//! Every day's Intcode program translated to Rust with `intcode::translate` when this crate is
+//! built, one module per day. Each module has a `computer()` function returning a fresh
+//! `NativeComputer` for the program.
+//!
+//! Days 13 and 17 change the first instruction of their program for part 2, which would make a
+//! `NativeComputer` for the original translation interpret everything from then on. The patched
+//! programs are translated as well, as `day13_part2` and `day17_part2`. Day 17 still hands over
+//! to the interpreter once it reads the movement routine, which it stores where the translation
+//! found code.
+
+macro_rules! translated {
+    ($($day:ident),*) => {
+        $(
+            pub mod $day {
+                include!(concat!(env!("OUT_DIR"), "/", stringify!($day), ".rs"));
+            }
+        )*
+    };
+}
+
+translated!(
+    day02,
+    day05,
+    day07,
+    day09,
+    day11,
+    day13,
+    day13_part2,
+    day15,
+    day17,
+    day17_part2,
+    day19,
+    day23
+);
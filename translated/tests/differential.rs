@@ -0,0 +1,239 @@
+//! Runs every day's program both translated and interpreted, with the same inputs, and checks
+//! that they go through the same states and end up with the same memory.
+
+use intcode::{Computer, ComputerState, NativeComputer, Translation};
+use translated::*;
+
+/// One run of a program, driven the same way for both computers.
+#[derive(Default)]
+struct Run {
+    /// Memory changes made before running, like the days do.
+    patches: &'static [(usize, i64)],
+    inputs: Vec<i64>,
+    /// Values to pick from whenever the program wants input that `inputs` didn't provide. When
+    /// this is empty the run ends at the first such request.
+    choices: &'static [i64],
+    /// Maximum number of states before the run is cut short.
+    limit: usize,
+}
+
+// Whether the computer stayed with the translated code, or handed over to the interpreter for
+// good at some point.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Mode {
+    Translated,
+    Interpreted,
+}
+
+fn check(name: &str, translation: &'static Translation, run: Run) -> Mode {
+    let mut native = NativeComputer::new(translation);
+    let mut interpreter = Computer::new(translation.program.to_vec());
+    for &(address, value) in run.patches {
+        native.set_memory(address, value);
+        interpreter.set_memory(address, value);
+    }
+    for &input in &run.inputs {
+        native.push_input(input);
+        interpreter.push_input(input);
+    }
+
+    // A fixed xorshift sequence, so every run picks the same inputs.
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let limit = if run.limit == 0 { 10_000 } else { run.limit };
+    for state in 0..limit {
+        let expected = interpreter.execute();
+        assert_eq!(
+            native.execute(),
+            expected,
+            "{}: state {} differs",
+            name,
+            state
+        );
+        match expected {
+            Ok(ComputerState::InputRequired) if !run.choices.is_empty() => {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                let input = run.choices[(seed % run.choices.len() as u64) as usize];
+                native.push_input(input);
+                interpreter.push_input(input);
+            }
+            Ok(ComputerState::Output(_)) => {}
+            _ => break,
+        }
+    }
+
+    assert_eq!(native.ip(), interpreter.ip(), "{}: ip differs", name);
+    assert_eq!(
+        native.relative_base(),
+        interpreter.relative_base(),
+        "{}: relative base differs",
+        name
+    );
+    assert!(
+        native
+            .memory()
+            .nonzero_words()
+            .eq(interpreter.memory().nonzero_words()),
+        "{}: memory differs",
+        name
+    );
+    if native.is_interpreted() {
+        Mode::Interpreted
+    } else {
+        Mode::Translated
+    }
+}
+
+#[test]
+fn day02() {
+    // The program overwrites its own instructions straight away.
+    let run = Run {
+        patches: &[(1, 12), (2, 2)],
+        ..Run::default()
+    };
+    assert_eq!(check("day02", &day02::TRANSLATION, run), Mode::Interpreted);
+}
+
+#[test]
+fn day05() {
+    // Most of the program only becomes valid code once the input is added to the opcode of its
+    // third instruction. Testing system 5 also overwrites the first instruction.
+    for &(system, mode) in &[(1, Mode::Translated), (5, Mode::Interpreted)] {
+        let run = Run {
+            inputs: vec![system],
+            ..Run::default()
+        };
+        assert_eq!(check("day05", &day05::TRANSLATION, run), mode);
+    }
+}
+
+#[test]
+fn day07() {
+    for phase in 0..10 {
+        let run = Run {
+            inputs: vec![phase, 0],
+            choices: &[0, 1, 17, 123_456],
+            limit: 100,
+            ..Run::default()
+        };
+        assert_eq!(check("day07", &day07::TRANSLATION, run), Mode::Translated);
+    }
+}
+
+#[test]
+fn day09() {
+    for &mode in &[1, 2] {
+        let run = Run {
+            inputs: vec![mode],
+            ..Run::default()
+        };
+        assert_eq!(check("day09", &day09::TRANSLATION, run), Mode::Translated);
+    }
+}
+
+#[test]
+fn day11() {
+    for &start in &[0, 1] {
+        let run = Run {
+            inputs: vec![start],
+            choices: &[0, 1],
+            limit: 20_000,
+            ..Run::default()
+        };
+        assert_eq!(check("day11", &day11::TRANSLATION, run), Mode::Translated);
+    }
+}
+
+#[test]
+fn day13() {
+    assert_eq!(
+        check("day13", &day13::TRANSLATION, Run::default()),
+        Mode::Translated
+    );
+    // Playing for free changes the first instruction, which hands the original translation over
+    // to the interpreter for good. The program translated with the patch stays native.
+    let run = || Run {
+        choices: &[-1, 0, 1],
+        limit: 50_000,
+        ..Run::default()
+    };
+    let patched = Run {
+        patches: &[(0, 2)],
+        ..run()
+    };
+    assert_eq!(
+        check("day13", &day13::TRANSLATION, patched),
+        Mode::Interpreted
+    );
+    assert_eq!(
+        check("day13_part2", &day13_part2::TRANSLATION, run()),
+        Mode::Translated
+    );
+}
+
+#[test]
+fn day15() {
+    let run = Run {
+        choices: &[1, 2, 3, 4],
+        limit: 20_000,
+        ..Run::default()
+    };
+    assert_eq!(check("day15", &day15::TRANSLATION, run), Mode::Translated);
+}
+
+#[test]
+fn day17() {
+    assert_eq!(
+        check("day17", &day17::TRANSLATION, Run::default()),
+        Mode::Translated
+    );
+    // Waking the robot up changes the first instruction, see day 13. Even the program translated
+    // with the patch hands over to the interpreter once it reads the movement routine, because
+    // it stores the routine at 1182, an address it also keeps in a variable like a function
+    // pointer, so the translation takes it for code. Building the map before that runs natively.
+    let routine = "A\nL,4\nR,4\nL,4\nn\n";
+    let run = || Run {
+        inputs: routine.bytes().map(i64::from).collect(),
+        limit: 20_000,
+        ..Run::default()
+    };
+    let patched = Run {
+        patches: &[(0, 2)],
+        ..run()
+    };
+    assert_eq!(
+        check("day17", &day17::TRANSLATION, patched),
+        Mode::Interpreted
+    );
+    assert_eq!(
+        check("day17_part2", &day17_part2::TRANSLATION, run()),
+        Mode::Interpreted
+    );
+}
+
+#[test]
+fn day19() {
+    for y in (0..50).step_by(7) {
+        for x in (0..50).step_by(3) {
+            let run = Run {
+                inputs: vec![x, y],
+                ..Run::default()
+            };
+            assert_eq!(check("day19", &day19::TRANSLATION, run), Mode::Translated);
+        }
+    }
+}
+
+#[test]
+fn day23() {
+    for address in 0..50 {
+        let run = Run {
+            inputs: vec![address],
+            choices: &[-1, -1, -1, 0, 17, 255],
+            limit: 1_000,
+            ..Run::default()
+        };
+        assert_eq!(check("day23", &day23::TRANSLATION, run), Mode::Translated);
+    }
+}